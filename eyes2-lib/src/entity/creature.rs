@@ -6,9 +6,13 @@
 //! 1. A creature can move one cell in any of the 8 directions (including diagonals)
 //! 2. A herbivore can eat grass if it is in the same cell as the grass
 //! 3. A carnivore can eat another creature if it is in the same cell as the other creature
-//! 4. A creature can reproduce if it has enough energy
+//!    and can scavenge the corpses left by creatures that have died
+//! 4. A creature can reproduce if it has enough energy, either alone or by
+//!    mating with an adjacent creature of a compatible genotype
 //! 5. A creature dies if it has no energy
 //! 6. A creature can request the value adjacent cells (i.e. the vision in 'eyes)
+//! 7. A creature can leave a scent trail which it and others can smell
+//! 8. A creature can broadcast a signal which nearby creatures will hear
 //!
 //! The rules are implemented in the tick() method which is called once per tick
 //! of the world. Global settings control the energy costs and rewards of each action.
@@ -26,8 +30,8 @@ use crate::utils::move_pos;
 use std::rc::Rc;
 use std::sync::mpsc;
 
-use super::genotype::genotype::GenotypeActions;
-use super::vision::Vision;
+use super::genotype::genotype::{DebugState, Diet, GenotypeActions, GenotypeInfo};
+use super::vision::{Scent, Vision};
 use super::Genotype;
 use super::Update;
 use crate::Settings;
@@ -51,12 +55,41 @@ pub struct Creature {
     // transmitter to send updates to the world (optional to support deserialisation)
     #[serde(skip)]
     tx: Option<Rc<mpsc::Sender<Update>>>,
-    // the world rules are different for herbivores and carnivores
-    _herbivore: bool,
     // the genotype of the creature which determines its behaviour
     genotype: Box<dyn Genotype>,
     // the sigil used to represent the creature in the world
     sigil: char,
+    // the tick the creature joined the world and the number of children it
+    // has had, the best genomes are picked by these when the world restarts
+    #[serde(default)]
+    born: Option<u64>,
+    #[serde(default)]
+    children: u32,
+    // when traced the creature records its last vision and action
+    #[serde(skip)]
+    traced: bool,
+    #[serde(skip)]
+    last_vision: Option<Vision>,
+    #[serde(skip)]
+    last_action: String,
+    #[serde(skip)]
+    last_action_kind: &'static str,
+}
+
+// A snapshot of a creature for the step debugger
+#[derive(Clone, Debug)]
+pub struct CreatureDebug {
+    pub id: u64,
+    pub sigil: char,
+    pub coord: Coord,
+    pub energy: i32,
+    pub info: GenotypeInfo,
+    pub state: DebugState,
+    // the last vision received (if any)
+    pub vision: Option<Vision>,
+    // the action chosen on the last tick and the kind of that action
+    pub action: String,
+    pub action_kind: String,
 }
 
 // The representation of a creature in the world
@@ -80,9 +113,34 @@ impl Creature {
             energy,
             config,
             tx: Some(tx),
-            _herbivore: true,
             genotype,
-            sigil: sigil,
+            sigil,
+            born: None,
+            children: 0,
+            traced: false,
+            last_vision: None,
+            last_action: String::new(),
+            last_action_kind: "",
+        }
+    }
+
+    // a creature that is not yet in a world e.g. for writing to a file
+    pub fn detached(genotype: Box<dyn Genotype>, config: Settings) -> Creature {
+        let sigil = genotype.get_sigil();
+        Creature {
+            id: 0,
+            coord: Coord::default(),
+            energy: config.creature_initial_energy.1,
+            config,
+            tx: None,
+            genotype,
+            sigil,
+            born: None,
+            children: 0,
+            traced: false,
+            last_vision: None,
+            last_action: String::new(),
+            last_action_kind: "",
         }
     }
 
@@ -90,6 +148,10 @@ impl Creature {
         self.id
     }
 
+    pub fn energy(&self) -> i32 {
+        self.energy
+    }
+
     pub fn coord(&self) -> Coord {
         self.coord
     }
@@ -105,6 +167,19 @@ impl Creature {
         }
     }
 
+    pub fn born(&self) -> u64 {
+        self.born.unwrap_or(0)
+    }
+
+    // a creature that is loaded into a world keeps the tick it was born
+    pub fn set_born(&mut self, tick: u64) {
+        self.born.get_or_insert(tick);
+    }
+
+    pub fn children(&self) -> u32 {
+        self.children
+    }
+
     pub fn set_tx(&mut self, tx: Rc<mpsc::Sender<Update>>) {
         // tx is immutable once set
         if self.tx.is_none() {
@@ -113,12 +188,14 @@ impl Creature {
     }
 
     pub fn set_config(&mut self, config: Settings) {
+        self.genotype.set_config(config.clone());
         self.config = config;
     }
 
     pub fn eat(&mut self, amount: i32) {
         self.energy += amount;
         self.genotype.set_energy(self.energy);
+        self.genotype.ate(amount);
     }
 
     pub fn tick(&mut self) {
@@ -135,11 +212,20 @@ impl Creature {
             return;
         }
 
-        // call the genotype specific tick method
-        match self.genotype.tick() {
+        // call the genotype specific tick method with the energy it has now
+        self.genotype.set_energy(self.energy);
+        let action = self.genotype.tick();
+        if self.traced {
+            self.last_action = action.to_string();
+            self.last_action_kind = action.kind();
+        }
+        match action {
             GenotypeActions::Move(direction) => self.move_dir(direction),
             GenotypeActions::Reproduce(genotype) => self.reproduce(genotype),
+            GenotypeActions::Mate => self.request_mate(),
             GenotypeActions::Look => self.look(),
+            GenotypeActions::Scent(amount) => self.deposit_scent(amount),
+            GenotypeActions::Signal(value) => self.send_signal(value),
             GenotypeActions::None => {}
         }
     }
@@ -148,8 +234,89 @@ impl Creature {
         self.sigil
     }
 
-    pub fn vision(&mut self, vision: Vision) {
-        self.genotype.vision(vision);
+    pub fn genotype(&self) -> &dyn Genotype {
+        self.genotype.as_ref()
+    }
+
+    // the world rules are different for herbivores and carnivores
+    pub fn diet(&self) -> Diet {
+        self.genotype.diet()
+    }
+
+    pub fn vision(&mut self, vision: Vision, scent: Scent) {
+        if self.traced {
+            self.last_vision = Some(vision);
+        }
+        self.genotype.vision(vision, scent);
+    }
+
+    // the genotype of a child of this creature and a partner, if they
+    // are compatible
+    pub fn crossover(&self, partner: &Creature) -> Option<Box<dyn Genotype>> {
+        self.genotype.crossover(partner.genotype.as_ref())
+    }
+
+    // make a child with a partner, each parent gives the child a quarter
+    // of its energy
+    pub fn mate(
+        &mut self,
+        partner: &mut Creature,
+        genotype: Box<dyn Genotype>,
+        coord: Coord,
+    ) -> Creature {
+        let mut child = Creature::new(
+            genotype,
+            coord,
+            self.config.clone(),
+            self.tx.as_mut().unwrap().clone(),
+        );
+        let (mine, theirs) = (self.energy / 4, partner.energy / 4);
+        self.energy -= mine;
+        partner.energy -= theirs;
+        self.genotype.set_energy(self.energy);
+        partner.genotype.set_energy(partner.energy);
+        self.children += 1;
+        partner.children += 1;
+        child.energy = mine + theirs;
+        child
+    }
+
+    // start or stop recording the creature's vision and actions
+    pub fn set_traced(&mut self, traced: bool) {
+        self.traced = traced;
+        if !traced {
+            self.last_vision = None;
+            self.last_action.clear();
+            self.last_action_kind = "";
+        }
+    }
+
+    // the instruction pointer and kind of the last action, which is all a
+    // breakpoint needs to know
+    pub fn ip(&self) -> Option<usize> {
+        self.genotype.ip()
+    }
+
+    pub fn action_kind(&self) -> &'static str {
+        self.last_action_kind
+    }
+
+    pub fn debug(&self) -> CreatureDebug {
+        CreatureDebug {
+            id: self.id,
+            sigil: self.sigil,
+            coord: self.coord,
+            energy: self.energy,
+            info: self.genotype.describe(),
+            state: self.genotype.debug_state(),
+            vision: self.last_vision,
+            action: self.last_action.clone(),
+            action_kind: self.last_action_kind.to_string(),
+        }
+    }
+
+    pub fn signal(&mut self, value: u8, from: Coord) {
+        self.genotype.signal(value, from - self.coord);
     }
 }
 
@@ -165,6 +332,7 @@ impl Creature {
         );
         self.energy /= 2;
         child.energy = self.energy;
+        self.children += 1;
         // child is spawned to the left unless we are against the left wall
         if self.coord.x == 0 {
             child.coord.x += 1;
@@ -174,10 +342,19 @@ impl Creature {
         self.tx
             .as_mut()
             .unwrap()
-            .send(Update::AddEntity(child))
+            .send(Update::AddEntity(Box::new(child)))
             .expect("creature reproduce failed");
     }
 
+    fn request_mate(&mut self) {
+        let id = self.id();
+        self.tx
+            .as_mut()
+            .unwrap()
+            .send(Update::Mate(id))
+            .expect("failed to send mate");
+    }
+
     fn move_dir(&mut self, direction: Direction) {
         let new_pos = move_pos(self.coord, direction, self.config.size);
 
@@ -190,6 +367,29 @@ impl Creature {
             .expect("failed to send move entity");
     }
 
+    fn deposit_scent(&mut self, amount: f32) {
+        // a genotype cannot take scent away or leave more than scent_max
+        if !amount.is_finite() || amount <= 0.0 {
+            return;
+        }
+        let amount = amount.min(self.config.scent_max);
+        self.energy -= self.config.scent_energy;
+        self.tx
+            .as_mut()
+            .unwrap()
+            .send(Update::Scent(self.coord, amount))
+            .expect("failed to send scent");
+    }
+
+    fn send_signal(&mut self, value: u8) {
+        self.energy -= self.config.signal_energy;
+        self.tx
+            .as_mut()
+            .unwrap()
+            .send(Update::Signal(self.id, self.coord, value))
+            .expect("failed to send signal");
+    }
+
     fn look(&mut self) {
         let id = self.id();
         self.tx
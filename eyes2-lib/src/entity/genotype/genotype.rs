@@ -1,7 +1,14 @@
-use direction::Direction;
+use direction::{Coord, Direction};
 use dyn_clone::{clone_trait_object, DynClone};
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::fmt;
+use std::hash::{Hash, Hasher};
 
-use crate::{entity::Vision, Settings};
+use crate::{
+    entity::{Scent, Vision},
+    Settings,
+};
 
 #[derive(Debug)]
 pub enum BadGenomeError {
@@ -13,36 +20,216 @@ pub enum BadGenomeError {
 // genome (with mutations as appropriate) will be passed to the
 // descendant creatures.
 #[typetag::serde(tag = "type")]
-pub trait Genotype: DynClone {
+pub trait Genotype: DynClone + Any {
     // execute the next instruction of your Genomic code
     fn tick(&mut self) -> GenotypeActions;
 
     // change your internal energy level (this is for reference only as
-    // the canonical energy level in in Creature itself)
+    // the canonical energy level in in Creature itself), called before
+    // every tick and whenever the energy changes between ticks
     fn set_energy(&mut self, energy: i32);
 
+    // A callback from the world when the creature eats, with the energy
+    // it gained. set_energy has already been called with the new level.
+    fn ate(&mut self, _energy: i32) {}
+
+    // replace your copy of the settings, the world calls this when the
+    // settings change while it runs and when it is loaded from a file
+    fn set_config(&mut self, _config: Settings) {}
+
     // return the sigil used to represent this creature in the world
     fn get_sigil(&self) -> char {
         'D'
     }
 
+    // return the kinds of food this creature can eat
+    fn diet(&self) -> Diet {
+        Diet::Herbivore
+    }
+
     // A callback from the world to return the view of the world from
     // the last Look(Direction) action. The value is a 1D array of 4
-    // Cells. With the nearest cell the first in the array.
-    fn vision(&mut self, _vision: Vision) {}
+    // Cells. With the nearest cell the first in the array. The scent
+    // holds the strength of the scent trail in each of the same cells.
+    fn vision(&mut self, _vision: Vision, _scent: Scent) {}
+
+    // A callback from the world when a nearby creature sends a Signal.
+    // The offset is the position of the sender relative to this creature.
+    fn signal(&mut self, _value: u8, _offset: Coord) {}
+
+    // Combine this genotype with a partner's to make the genotype of a
+    // child of the two. Return None if the partner is not compatible,
+    // by default genotypes cannot mate.
+    fn crossover(&self, _partner: &dyn Genotype) -> Option<Box<dyn Genotype>> {
+        None
+    }
+
+    // Return the internal state of the genotype for the step debugger,
+    // by default there is nothing to show.
+    fn debug_state(&self) -> DebugState {
+        DebugState::default()
+    }
+
+    // Return the instruction pointer of debug_state without building the
+    // rest of it, the debugger checks this on every tick for breakpoints.
+    fn ip(&self) -> Option<usize> {
+        None
+    }
+
+    // Return a structured description of the genotype for tools and the
+    // GUI, by default this describes a genotype with no genome.
+    fn describe(&self) -> GenotypeInfo {
+        GenotypeInfo::new(self.typetag_name())
+    }
+
+    // The distance between this genome and another's, 0 for identical
+    // genomes and about 1 for unrelated ones. None if the other is a
+    // different kind of genotype, such creatures are never one species.
+    // By default genomes are either identical or unrelated.
+    fn distance(&self, other: &dyn Genotype) -> Option<f32> {
+        let (mine, theirs) = (self.describe(), other.describe());
+        if mine.name != theirs.name {
+            return None;
+        }
+        match mine.genome_hash == theirs.genome_hash {
+            true => Some(0.0),
+            false => Some(1.0),
+        }
+    }
 }
 clone_trait_object!(Genotype);
 
+// A structured description of a genotype
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GenotypeInfo {
+    // the name used for the genotype in settings (see new_genotype)
+    pub name: String,
+    // the key parameters of the genotype as (name, value)
+    pub params: Vec<(String, String)>,
+    // the number of genes in the genome
+    pub genome_size: usize,
+    // a one line human readable summary of the genome
+    pub summary: String,
+    // a hash of the genome, identical genomes have the same hash
+    pub genome_hash: u64,
+}
+
+impl GenotypeInfo {
+    // a description with no genome, the name is the typetag name without
+    // the _genotype suffix
+    pub fn new(typetag_name: &str) -> GenotypeInfo {
+        GenotypeInfo {
+            name: typetag_name.trim_end_matches("_genotype").to_string(),
+            ..GenotypeInfo::default()
+        }
+    }
+}
+
+// FNV-1a, unlike DefaultHasher its output does not change between Rust
+// releases so genome hashes can be compared across runs
+struct FnvHasher(u64);
+
+impl Hasher for FnvHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+// a stable hash of a genome for GenotypeInfo::genome_hash
+pub fn hash_genome<T: Hash + ?Sized>(genome: &T) -> u64 {
+    let mut hasher = FnvHasher(0xcbf2_9ce4_8422_2325);
+    genome.hash(&mut hasher);
+    hasher.finish()
+}
+
+// The internal state of a genotype as shown by the step debugger
+#[derive(Clone, Debug, Default)]
+pub struct DebugState {
+    // the address of the next instruction, for genotypes that have one
+    pub ip: Option<usize>,
+    // the name and value of each register or other internal variable
+    pub registers: Vec<(String, i32)>,
+    // a listing of the genome with one line per address
+    pub listing: Vec<String>,
+}
+
+// return the partner in a crossover as the concrete genotype T, or None if
+// the partner is some other kind of genotype
+pub fn partner_as<T: Genotype>(partner: &dyn Genotype) -> Option<&T> {
+    (partner as &dyn Any).downcast_ref::<T>()
+}
+
 // The genotype's tick method returns one of these actions. Creature
 // will pass the request on to the world which will verify the
 // action is valid and then update the world state accordingly.
 pub enum GenotypeActions {
     Reproduce(Box<dyn Genotype>),
+    // mate with an adjacent compatible creature
+    Mate,
     Move(Direction),
     Look,
+    // leave scent of the given strength in the creature's cell
+    Scent(f32),
+    // broadcast a value to all creatures within signal_radius
+    Signal(u8),
     None,
 }
 
+impl GenotypeActions {
+    // the name of the kind of action, used for debugger breakpoints
+    pub fn kind(&self) -> &'static str {
+        match self {
+            GenotypeActions::Reproduce(_) => "reproduce",
+            GenotypeActions::Mate => "mate",
+            GenotypeActions::Move(_) => "move",
+            GenotypeActions::Look => "look",
+            GenotypeActions::Scent(_) => "scent",
+            GenotypeActions::Signal(_) => "signal",
+            GenotypeActions::None => "none",
+        }
+    }
+}
+
+impl fmt::Display for GenotypeActions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GenotypeActions::Move(direction) => write!(f, "move {:?}", direction),
+            GenotypeActions::Scent(amount) => write!(f, "scent {:.2}", amount),
+            GenotypeActions::Signal(value) => write!(f, "signal {}", value),
+            action => write!(f, "{}", action.kind()),
+        }
+    }
+}
+
+// What a creature eats. A creature cannot move into a cell holding food
+// that is not part of its diet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Diet {
+    // eats plants
+    #[default]
+    Herbivore,
+    // scavenges corpses
+    Carnivore,
+    // eats plants and corpses
+    Omnivore,
+}
+
+impl Diet {
+    pub fn eats_plants(&self) -> bool {
+        matches!(self, Diet::Herbivore | Diet::Omnivore)
+    }
+
+    pub fn eats_corpses(&self) -> bool {
+        matches!(self, Diet::Carnivore | Diet::Omnivore)
+    }
+}
+
 // For each new Genotype defined the developer must add an arm to this
 // genotype constructor function. This constructor provides a polymorphic
 // interface to the Genotype trait.
@@ -51,8 +238,16 @@ pub fn new_genotype(which: &str, config: Settings) -> Result<Box<dyn Genotype>,
         "giles" => Box::new(super::genotypes::giles::GilesGenotype::new(config)),
         "noop" => Box::new(super::genotypes::noop::NoopGenotype::new(config)),
         "random" => Box::new(super::genotypes::random::RandomGenotype::new(config)),
+        "scavenger" => Box::new(super::genotypes::random::RandomGenotype::scavenger(config)),
         "looker" => Box::new(super::genotypes::looker::LookerGenotype::new(config)),
+        "neural" => Box::new(super::genotypes::neural::NeuralGenotype::new(config)),
+        "neat" => Box::new(super::genotypes::neat::NeatGenotype::new(config)),
+        "fsm" => Box::new(super::genotypes::fsm::FsmGenotype::new(config)),
         _ => return Err(BadGenomeError::InvalidGenome),
     };
     Ok(genotype)
 }
+
+#[path = "genotype_test.rs"]
+#[cfg(test)]
+mod test;
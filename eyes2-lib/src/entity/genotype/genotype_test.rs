@@ -0,0 +1,53 @@
+use super::*;
+
+const NAMES: [&str; 8] = [
+    "giles",
+    "noop",
+    "random",
+    "scavenger",
+    "looker",
+    "neural",
+    "neat",
+    "fsm",
+];
+
+#[test]
+fn check_genotypes_describe_themselves() {
+    for name in NAMES {
+        let genotype = new_genotype(name, Settings::default()).unwrap();
+        let info = genotype.describe();
+        assert_eq!(info.name, name);
+
+        // a copy has the same genome and so the same hash
+        let copy = dyn_clone::clone_box(&*genotype);
+        assert_eq!(copy.describe().genome_hash, info.genome_hash);
+    }
+}
+
+#[test]
+fn check_genome_hash_differs_between_genomes() {
+    let a = new_genotype("giles", Settings::default()).unwrap();
+    let b = new_genotype("giles", Settings::default()).unwrap();
+    assert_eq!(a.describe().genome_size, b.describe().genome_size);
+    assert_ne!(a.describe().genome_hash, b.describe().genome_hash);
+}
+
+#[test]
+fn check_genome_distance() {
+    let a = new_genotype("giles", Settings::default()).unwrap();
+    let b = new_genotype("giles", Settings::default()).unwrap();
+    let copy = dyn_clone::clone_box(&*a);
+    assert_eq!(a.distance(copy.as_ref()), Some(0.0));
+    assert!(a.distance(b.as_ref()).unwrap() > 0.5);
+
+    // different kinds of genotype are never the same species
+    let fsm = new_genotype("fsm", Settings::default()).unwrap();
+    assert_eq!(a.distance(fsm.as_ref()), None);
+    assert_eq!(fsm.distance(fsm.as_ref()), Some(0.0));
+}
+
+#[test]
+fn check_genome_hash_is_stable() {
+    // the published FNV-1a test vector for "a"
+    assert_eq!(hash_genome(&b'a'), 0xaf63_dc4c_8601_ec8c);
+}
@@ -0,0 +1,373 @@
+//! Implement the FSM genotype, an evolvable finite state machine.
+//!
+//! The genome is a table of states. Each state has an action and a next
+//! state for each class of cell that the creature can see ahead of it:
+//! empty, grass, another creature, a wall or an empty cell that smells of
+//! scent. The creature keeps a heading and its moves are relative to that
+//! heading, it can also mark its cell with scent.
+//!
+//! On reproduction the child may mutate any entry of the table and may gain
+//! or lose a state.
+
+use super::{hash_genome, partner_as, DebugState, Genotype, GenotypeActions, GenotypeInfo};
+use crate::entity::{get_vision_in_direction, Scent, Vision};
+use crate::{Cell, Settings};
+use direction::Direction;
+use fastrand::Rng as FastRng;
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const INITIAL_STATES: usize = 4;
+// the machine takes a step once every STEP_TICKS
+const STEP_TICKS: u64 = 100;
+
+// the classes of cell that select a transition
+const CLASSES: usize = 5;
+const CLASS_NAMES: [&str; CLASSES] = ["empty", "grass", "entity", "wall", "scent"];
+const SCENT: usize = 4;
+
+// the action a state emits, moves are relative to the heading
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FsmAction {
+    Forward,
+    Left,
+    Right,
+    Back,
+    Look,
+    Mate,
+    Wait,
+    Mark,
+}
+
+const ACTIONS: [FsmAction; 8] = [
+    FsmAction::Forward,
+    FsmAction::Left,
+    FsmAction::Right,
+    FsmAction::Back,
+    FsmAction::Look,
+    FsmAction::Mate,
+    FsmAction::Wait,
+    FsmAction::Mark,
+];
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FsmState {
+    pub action: FsmAction,
+    // the next state for each class of cell ahead
+    #[serde(deserialize_with = "transitions")]
+    pub next: [usize; CLASSES],
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(try_from = "SavedFsm")]
+pub struct FsmGenotype {
+    #[serde(skip)]
+    config: Settings,
+    energy: i32,
+    #[serde(skip)]
+    rng: FastRng,
+    // the genome
+    states: Vec<FsmState>,
+    state: usize,
+    heading: Direction,
+    // the class of the cell ahead in the last vision
+    ahead: usize,
+    next_tick: u64,
+}
+
+// the saved fields of an FsmGenotype, they are checked before they are
+// used so that a corrupt save is an error rather than a panic
+#[derive(Deserialize)]
+struct SavedFsm {
+    energy: i32,
+    states: Vec<FsmState>,
+    state: usize,
+    heading: Direction,
+    ahead: usize,
+    next_tick: u64,
+}
+
+impl TryFrom<SavedFsm> for FsmGenotype {
+    type Error = String;
+
+    fn try_from(saved: SavedFsm) -> Result<Self, Self::Error> {
+        let count = saved.states.len();
+        if count == 0 {
+            return Err("an fsm genome needs at least one state".to_string());
+        }
+        if saved.state >= count {
+            return Err(format!(
+                "fsm state {} is not one of the {} states",
+                saved.state, count
+            ));
+        }
+        if saved.ahead >= CLASSES {
+            return Err(format!(
+                "fsm class {} is not one of the classes",
+                saved.ahead
+            ));
+        }
+        for (i, state) in saved.states.iter().enumerate() {
+            if let Some(next) = state.next.iter().find(|next| **next >= count) {
+                return Err(format!(
+                    "fsm state {} goes to state {} but there are {} states",
+                    i, next, count
+                ));
+            }
+        }
+        Ok(FsmGenotype {
+            config: Settings::default(),
+            energy: saved.energy,
+            rng: FastRng::new(),
+            states: saved.states,
+            state: saved.state,
+            heading: saved.heading,
+            ahead: saved.ahead,
+            next_tick: saved.next_tick,
+        })
+    }
+}
+
+#[typetag::serde(name = "fsm_genotype")]
+impl Genotype for FsmGenotype {
+    fn tick(&mut self) -> GenotypeActions {
+        if self.energy >= self.config.creature_reproduction_energy {
+            return GenotypeActions::Reproduce(Box::new(self.reproduce()));
+        }
+        if self.next_tick > 0 {
+            self.next_tick -= 1;
+            return GenotypeActions::None;
+        }
+        self.next_tick = STEP_TICKS;
+
+        let state = &self.states[self.state];
+        let action = state.action;
+        self.state = state.next[self.ahead];
+
+        self.heading = match action {
+            FsmAction::Left => self.heading.left90(),
+            FsmAction::Right => self.heading.right90(),
+            FsmAction::Back => self.heading.opposite(),
+            _ => self.heading,
+        };
+        match action {
+            FsmAction::Look => GenotypeActions::Look,
+            FsmAction::Mate => GenotypeActions::Mate,
+            FsmAction::Wait => GenotypeActions::None,
+            FsmAction::Mark => GenotypeActions::Scent(self.config.scent_max),
+            _ => {
+                // we no longer know what is ahead after a move
+                self.ahead = 0;
+                GenotypeActions::Move(self.heading)
+            }
+        }
+    }
+
+    fn set_energy(&mut self, energy: i32) {
+        self.energy = energy;
+    }
+
+    fn set_config(&mut self, config: Settings) {
+        self.config = config;
+    }
+
+    fn get_sigil(&self) -> char {
+        'F'
+    }
+
+    fn vision(&mut self, vision: Vision, scent: Scent) {
+        self.ahead = match get_vision_in_direction(vision, &self.heading) {
+            Cell::Empty if scent[self.heading as usize] > 0.0 => SCENT,
+            Cell::Empty => 0,
+            Cell::Grass(_, _) => 1,
+            Cell::Entity(_, _) => 2,
+            Cell::Wall | Cell::Corpse(_) => 3,
+        };
+    }
+
+    // each state comes from either parent, the child has this parent's
+    // number of states
+    fn crossover(&self, partner: &dyn Genotype) -> Option<Box<dyn Genotype>> {
+        let partner = partner_as::<FsmGenotype>(partner)?;
+        let mut child = self.clone();
+        let count = child.states.len();
+        for (state, their_state) in child.states.iter_mut().zip(partner.states.iter()) {
+            if self.rng.bool() {
+                state.action = their_state.action;
+                state.next = their_state.next.map(|next| next % count);
+            }
+        }
+        child.energy = 0;
+        child.state = 0;
+        child.mutate();
+        Some(Box::new(child))
+    }
+
+    // the current state is the instruction pointer
+    fn debug_state(&self) -> DebugState {
+        DebugState {
+            ip: self.ip(),
+            registers: vec![
+                ("heading".to_string(), self.heading as i32),
+                ("ahead".to_string(), self.ahead as i32),
+                ("wait".to_string(), self.next_tick as i32),
+            ],
+            listing: self.states.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    fn ip(&self) -> Option<usize> {
+        Some(self.state)
+    }
+
+    // the fraction of entries in the state tables that differ, extra
+    // states count as entirely different
+    fn distance(&self, other: &dyn Genotype) -> Option<f32> {
+        let other = partner_as::<FsmGenotype>(other)?;
+        let entries = CLASSES + 1;
+        let len = self.states.len().max(other.states.len());
+        let mut different = (len - self.states.len().min(other.states.len())) * entries;
+        for (mine, theirs) in self.states.iter().zip(other.states.iter()) {
+            different += (mine.action != theirs.action) as usize;
+            different += mine
+                .next
+                .iter()
+                .zip(theirs.next)
+                .filter(|(a, b)| **a != *b)
+                .count();
+        }
+        Some(different as f32 / (len * entries).max(1) as f32)
+    }
+
+    fn describe(&self) -> GenotypeInfo {
+        let mut actions: Vec<FsmAction> = self.states.iter().map(|s| s.action).collect();
+        actions.sort_by_key(|a| *a as usize);
+        actions.dedup();
+        GenotypeInfo {
+            params: vec![(
+                "max_states".to_string(),
+                self.config.fsm_max_states.to_string(),
+            )],
+            genome_size: self.states.len(),
+            summary: format!("{} states using {:?}", self.states.len(), actions),
+            genome_hash: hash_genome(&self.states),
+            ..GenotypeInfo::new(self.typetag_name())
+        }
+    }
+}
+
+impl FsmGenotype {
+    pub fn new(config: Settings) -> FsmGenotype {
+        let rng = FastRng::new();
+        let states = (0..INITIAL_STATES)
+            .map(|_| random_state(&rng, INITIAL_STATES))
+            .collect();
+
+        FsmGenotype {
+            config,
+            energy: 0,
+            rng,
+            states,
+            state: 0,
+            heading: Direction::North,
+            ahead: 0,
+            next_tick: STEP_TICKS,
+        }
+    }
+
+    pub fn reproduce(&mut self) -> Self {
+        self.energy -= self.config.creature_reproduction_energy;
+        let mut child = self.clone();
+        child.mutate();
+        child.state = 0;
+        child
+    }
+
+    fn mutate(&mut self) {
+        let rate = self.config.fsm_mutation_rate;
+        let max_states = self.config.fsm_max_states as usize;
+
+        if self.rng.f32() < rate && self.states.len() < max_states {
+            let count = self.states.len() + 1;
+            self.states.push(random_state(&self.rng, count));
+            // link the new state into the machine
+            let from = self.rng.usize(0..count - 1);
+            self.states[from].next[self.rng.usize(0..CLASSES)] = count - 1;
+        }
+        if self.rng.f32() < rate && self.states.len() > 1 {
+            self.remove_state(self.rng.usize(0..self.states.len()));
+        }
+
+        let count = self.states.len();
+        for state in self.states.iter_mut() {
+            if self.rng.f32() < rate {
+                state.action = ACTIONS[self.rng.usize(0..ACTIONS.len())];
+            }
+            for next in state.next.iter_mut() {
+                if self.rng.f32() < rate {
+                    *next = self.rng.usize(0..count);
+                }
+            }
+        }
+    }
+
+    // transitions to the removed state go to the one that replaced it, or
+    // the new last state if the last was removed
+    fn remove_state(&mut self, removed: usize) {
+        self.states.remove(removed);
+        let count = self.states.len();
+        for state in self.states.iter_mut() {
+            for next in state.next.iter_mut() {
+                if *next > removed || *next == count {
+                    *next -= 1;
+                }
+            }
+        }
+    }
+}
+
+// States saved before the scent class was added have one transition
+// fewer, an empty cell that smells of scent takes them where an empty cell
+// did so that they behave as they did.
+fn transitions<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[usize; CLASSES], D::Error> {
+    let mut next = Vec::<usize>::deserialize(deserializer)?;
+    if next.len() == SCENT {
+        next.push(next[0]);
+    }
+    next.try_into()
+        .map_err(|next: Vec<usize>| de::Error::invalid_length(next.len(), &"5 transitions"))
+}
+
+fn random_state(rng: &FastRng, count: usize) -> FsmState {
+    FsmState {
+        action: ACTIONS[rng.usize(0..ACTIONS.len())],
+        next: [(); CLASSES].map(|_| rng.usize(0..count)),
+    }
+}
+
+// a state's action and transitions
+impl fmt::Display for FsmState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:<8}", format!("{:?}", self.action))?;
+        for (class, next) in CLASS_NAMES.iter().zip(self.next.iter()) {
+            write!(f, " {}->{}", class, next)?;
+        }
+        Ok(())
+    }
+}
+
+// a readable listing of the state table
+impl fmt::Display for FsmGenotype {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, state) in self.states.iter().enumerate() {
+            let current = if i == self.state { '>' } else { ' ' };
+            writeln!(f, "{}{:>2}: {}", current, i, state)?;
+        }
+        Ok(())
+    }
+}
+
+#[path = "fsm_test.rs"]
+#[cfg(test)]
+mod test;
@@ -0,0 +1,134 @@
+use super::*;
+
+fn get_config() -> Settings {
+    Settings {
+        fsm_mutation_rate: 0.5,
+        fsm_max_states: 8,
+        ..Settings::default()
+    }
+}
+
+fn state(action: FsmAction, next: [usize; CLASSES]) -> FsmState {
+    FsmState { action, next }
+}
+
+#[test]
+fn check_mutations_keep_the_table_valid() {
+    let mut genotype = FsmGenotype::new(get_config());
+    let mut sizes = std::collections::HashSet::new();
+    for _ in 0..500 {
+        genotype.mutate();
+        let count = genotype.states.len();
+        sizes.insert(count);
+
+        assert!((1..=8).contains(&count));
+        for state in genotype.states.iter() {
+            assert!(state.next.iter().all(|next| *next < count));
+        }
+    }
+    // states are both added and removed
+    assert!(sizes.len() > 2);
+}
+
+#[test]
+fn check_removing_a_state_remaps_transitions() {
+    let mut genotype = FsmGenotype::new(get_config());
+    genotype.states = vec![
+        state(FsmAction::Forward, [0, 1, 2, 3, 0]),
+        state(FsmAction::Left, [1, 1, 1, 1, 1]),
+        state(FsmAction::Right, [3, 2, 1, 0, 2]),
+        state(FsmAction::Look, [3, 3, 0, 2, 1]),
+    ];
+
+    // the state after the removed one takes its place
+    genotype.remove_state(1);
+    assert_eq!(
+        genotype.states[0],
+        state(FsmAction::Forward, [0, 1, 1, 2, 0])
+    );
+    assert_eq!(genotype.states[1], state(FsmAction::Right, [2, 1, 1, 0, 1]));
+    assert_eq!(genotype.states[2], state(FsmAction::Look, [2, 2, 0, 1, 1]));
+
+    // the new last state takes the place of a removed last state
+    genotype.remove_state(2);
+    assert_eq!(
+        genotype.states[0],
+        state(FsmAction::Forward, [0, 1, 1, 1, 0])
+    );
+    assert_eq!(genotype.states[1], state(FsmAction::Right, [1, 1, 1, 0, 1]));
+}
+
+#[test]
+fn check_crossover_mixes_the_parents_states() {
+    let config = Settings {
+        fsm_mutation_rate: 0.0,
+        ..get_config()
+    };
+    let mut mine = FsmGenotype::new(config.clone());
+    let mut theirs = FsmGenotype::new(config);
+    mine.states = vec![state(FsmAction::Forward, [0, 1, 0, 1, 0]); 2];
+    theirs.states = vec![state(FsmAction::Wait, [2, 3, 4, 1, 3]); 5];
+
+    let mut from = [0, 0];
+    for _ in 0..20 {
+        let child = mine.crossover(&theirs).unwrap();
+        let child = partner_as::<FsmGenotype>(child.as_ref()).unwrap();
+        // the child has this parent's number of states and only valid
+        // transitions
+        assert_eq!(child.states.len(), 2);
+        for state in child.states.iter() {
+            match state.action {
+                FsmAction::Forward => from[0] += 1,
+                FsmAction::Wait => {
+                    assert_eq!(state.next, [0, 1, 0, 1, 1]);
+                    from[1] += 1;
+                }
+                other => panic!("{:?} is in neither parent", other),
+            }
+        }
+    }
+    assert!(from[0] > 0 && from[1] > 0);
+
+    // other genotypes cannot mate with an fsm
+    let noop = crate::entity::new_genotype("noop", Settings::default()).unwrap();
+    assert!(mine.crossover(noop.as_ref()).is_none());
+}
+
+#[test]
+fn check_scent_ahead_is_a_class() {
+    let mut genotype = FsmGenotype::new(get_config());
+    let mut scent = [0.0; 8];
+    genotype.vision([Cell::Empty; 8], scent);
+    assert_eq!(genotype.ahead, 0);
+    scent[genotype.heading as usize] = 1.0;
+    genotype.vision([Cell::Empty; 8], scent);
+    assert_eq!(genotype.ahead, SCENT);
+
+    // states saved before the scent class follow the empty transition
+    let old: FsmState = serde_yaml::from_str("action: Mark\nnext: [2, 0, 1, 3]").unwrap();
+    assert_eq!(old, state(FsmAction::Mark, [2, 0, 1, 3, 2]));
+    assert!(serde_yaml::from_str::<FsmState>("action: Mark\nnext: [2, 0]").is_err());
+}
+
+#[test]
+fn check_corrupt_genomes_are_rejected() {
+    let genotype = FsmGenotype::new(get_config());
+    let text = serde_yaml::to_string(&genotype).unwrap();
+    let loaded: FsmGenotype = serde_yaml::from_str(&text).unwrap();
+    assert_eq!(loaded.states, genotype.states);
+
+    let load = |edit: &dyn Fn(&mut serde_yaml::Value)| {
+        let mut value = serde_yaml::to_value(&genotype).unwrap();
+        edit(&mut value);
+        serde_yaml::from_value::<FsmGenotype>(value)
+            .err()
+            .map(|e| e.to_string())
+    };
+    let error = load(&|v| v["state"] = 4.into()).unwrap();
+    assert!(error.contains("fsm state 4 is not one of the 4 states"));
+    let error = load(&|v| v["states"][2]["next"][1] = 9.into()).unwrap();
+    assert!(error.contains("fsm state 2 goes to state 9"));
+    let error = load(&|v| v["states"] = serde_yaml::Value::Sequence(vec![])).unwrap();
+    assert!(error.contains("at least one state"));
+    assert!(load(&|v| v["ahead"] = 5.into()).is_some());
+}
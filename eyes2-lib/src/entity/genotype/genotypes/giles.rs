@@ -4,75 +4,350 @@
 //! The original code is here: https://github.com/gilesknap/eyes
 //! (its not that pretty - hopefully my coding has improved in the last 20 years!)
 //!
-//! TODO this is still work in progress
+//! The genome is a block of RISC style instructions operating on 8 registers.
+//! One instruction is executed per tick, some instructions are requests to
+//! the world (look, move, breed, mate, signal) and others read the results
+//! back into the registers (see, energy, hear).
 //!
+//! A new creature gets a random genome. On reproduction each instruction of
+//! the child may be replaced by a random instruction.
+//!
+//! See giles_asm.rs for the text assembly format.
+//!
+use fastrand::Rng as FastRng;
 use serde::{Deserialize, Serialize};
 
-use super::{Genotype, GenotypeActions};
-use crate::Settings;
+use super::giles_asm::disassemble;
+use super::{hash_genome, partner_as, DebugState, Genotype, GenotypeActions, GenotypeInfo};
+use crate::entity::{Scent, Vision};
+use crate::utils::int_to_dir;
+use crate::{Cell, Settings};
+use direction::Coord;
 
-const _GENOME: usize = 1000;
+const GENOME: usize = 64;
+// jump addresses are u16 so a genome may have at most this many instructions
+pub const MAX_GENOME: usize = u16::MAX as usize;
+pub const REGISTERS: usize = 8;
+// energy is reported to the genome in units of ENERGY_SCALE
+const ENERGY_SCALE: i32 = 1000;
+
+// the instruction set, register operands are indexes 0..REGISTERS and
+// jumps are to absolute addresses in the genome
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Instruction {
+    // do nothing
+    Nop,
+    // r = value
+    Set(u8, i16),
+    // r = s
+    Mov(u8, u8),
+    // r = r + s
+    Add(u8, u8),
+    // r = r - s
+    Sub(u8, u8),
+    // r = r + 1
+    Inc(u8),
+    // r = r - 1
+    Dec(u8),
+    // jump to address
+    Jmp(u16),
+    // jump to address if r is zero
+    Jz(u8, u16),
+    // jump to address if r is not zero
+    Jnz(u8, u16),
+    // ask the world for the adjacent cells
+    Look,
+    // r = the class of the cell in direction s from the last look
+    // (0 empty, 1 grass, 2 creature, 3 wall or corpse)
+    See(u8, u8),
+    // move one cell in direction r
+    Move(u8),
+    // r = energy in units of 1000
+    Energy(u8),
+    // r = random number 0..256
+    Rand(u8),
+    // reproduce if there is enough energy
+    Breed,
+    // mate with an adjacent creature
+    Mate,
+    // broadcast the value of r
+    Signal(u8),
+    // r = the last signal heard (0 if none) and forget it
+    Hear(u8),
+}
+
+impl Instruction {
+    pub fn random(rng: &FastRng, len: usize) -> Instruction {
+        let r = || rng.u8(0..REGISTERS as u8);
+        let addr = || rng.u16(0..len.min(MAX_GENOME) as u16);
+        match rng.u8(0..19) {
+            0 => Instruction::Nop,
+            1 => Instruction::Set(r(), rng.i16(-8..8)),
+            2 => Instruction::Mov(r(), r()),
+            3 => Instruction::Add(r(), r()),
+            4 => Instruction::Sub(r(), r()),
+            5 => Instruction::Inc(r()),
+            6 => Instruction::Dec(r()),
+            7 => Instruction::Jmp(addr()),
+            8 => Instruction::Jz(r(), addr()),
+            9 => Instruction::Jnz(r(), addr()),
+            10 => Instruction::Look,
+            11 => Instruction::See(r(), r()),
+            12 => Instruction::Move(r()),
+            13 => Instruction::Energy(r()),
+            14 => Instruction::Rand(r()),
+            15 => Instruction::Breed,
+            16 => Instruction::Mate,
+            17 => Instruction::Signal(r()),
+            _ => Instruction::Hear(r()),
+        }
+    }
+}
 
 #[derive(Serialize, Deserialize, Clone)]
-#[allow(dead_code)] // TODO remove this when we have a real instruction set
 pub struct GilesGenotype {
     #[serde(skip)]
     config: Settings,
+    #[serde(skip)]
+    rng: FastRng,
     // energy level
     energy: i32,
-    ip: u16, // instruction pointer
-    a: u16,  // accumulator
-    // i: [u16; 5],           // registers
-    breed_rate: u32, // rate at which the creature breeds
-    mutation_rate: u32, // rate at which the creature mutates
-                     // genome: [u16; GENOME], // the genome i.e. the instructions to be executed
+    // instruction pointer
+    ip: u16,
+    // registers
+    r: [i16; REGISTERS],
+    // the cell classes from the last look
+    sight: [i16; 8],
+    // the last signal heard
+    heard: i16,
+    // chance that each instruction mutates on reproduction
+    mutation_rate: f32,
+    // the genome i.e. the instructions to be executed
+    genome: Vec<Instruction>,
 }
 
 #[typetag::serde(name = "giles_genotype")]
 impl Genotype for GilesGenotype {
     fn tick(&mut self) -> GenotypeActions {
-        GenotypeActions::None
+        if self.genome.is_empty() {
+            return GenotypeActions::None;
+        }
+        let instruction = self.genome[self.ip as usize % self.genome.len()];
+        self.ip = ((self.ip as usize + 1) % self.genome.len()) as u16;
+        self.execute(instruction)
     }
 
-    fn set_energy(&mut self, _energy: i32) {}
+    fn set_energy(&mut self, energy: i32) {
+        self.energy = energy;
+    }
+
+    fn set_config(&mut self, config: Settings) {
+        self.config = config;
+    }
 
     fn get_sigil(&self) -> char {
         'G'
     }
+
+    fn vision(&mut self, vision: Vision, _scent: Scent) {
+        for (i, cell) in vision.iter().enumerate() {
+            self.sight[i] = match cell {
+                Cell::Empty => 0,
+                Cell::Grass(_, _) => 1,
+                Cell::Entity(_, _) => 2,
+                Cell::Wall | Cell::Corpse(_) => 3,
+            };
+        }
+    }
+
+    fn signal(&mut self, value: u8, _offset: Coord) {
+        self.heard = value as i16;
+    }
+
+    // single point crossover of the two genomes
+    fn crossover(&self, partner: &dyn Genotype) -> Option<Box<dyn Genotype>> {
+        let partner = partner_as::<GilesGenotype>(partner)?;
+        let mut child = self.child();
+        let point = self.rng.usize(0..=child.genome.len());
+        for (i, instruction) in partner.genome.iter().enumerate().skip(point) {
+            if i < child.genome.len() {
+                child.genome[i] = *instruction;
+            }
+        }
+        child.mutate();
+        Some(Box::new(child))
+    }
+
+    fn ip(&self) -> Option<usize> {
+        Some(self.ip as usize)
+    }
+
+    fn debug_state(&self) -> DebugState {
+        let mut registers: Vec<(String, i32)> = (0..REGISTERS)
+            .map(|r| (format!("r{}", r), self.r[r] as i32))
+            .collect();
+        registers.push(("heard".to_string(), self.heard as i32));
+        DebugState {
+            ip: Some(self.ip as usize),
+            registers,
+            listing: disassemble(&self.genome)
+                .lines()
+                .map(String::from)
+                .collect(),
+        }
+    }
+
+    // the fraction of addresses that hold different instructions
+    fn distance(&self, other: &dyn Genotype) -> Option<f32> {
+        let other = partner_as::<GilesGenotype>(other)?;
+        let len = self.genome.len().max(other.genome.len());
+        let same = self
+            .genome
+            .iter()
+            .zip(other.genome.iter())
+            .filter(|(mine, theirs)| mine == theirs)
+            .count();
+        match len {
+            0 => Some(0.0),
+            _ => Some((len - same) as f32 / len as f32),
+        }
+    }
+
+    fn describe(&self) -> GenotypeInfo {
+        let count = |f: fn(&Instruction) -> bool| self.genome.iter().filter(|i| f(i)).count();
+        let moves = count(|i| matches!(i, Instruction::Move(_)));
+        let looks = count(|i| matches!(i, Instruction::Look));
+        let breeds = count(|i| matches!(i, Instruction::Breed | Instruction::Mate));
+        GenotypeInfo {
+            params: vec![
+                ("registers".to_string(), REGISTERS.to_string()),
+                ("mutation_rate".to_string(), self.mutation_rate.to_string()),
+            ],
+            genome_size: self.genome.len(),
+            summary: format!(
+                "{} instructions, {} move, {} look, {} breed",
+                self.genome.len(),
+                moves,
+                looks,
+                breeds
+            ),
+            genome_hash: hash_genome(&self.genome),
+            ..GenotypeInfo::new(self.typetag_name())
+        }
+    }
 }
 
 impl GilesGenotype {
     pub fn new(config: Settings) -> GilesGenotype {
+        let rng = FastRng::new();
+        let genome = (0..GENOME)
+            .map(|_| Instruction::random(&rng, GENOME))
+            .collect();
+        GilesGenotype::with_genome(config, genome, rng)
+    }
+
+    // create a creature that runs the given program, any instructions
+    // beyond MAX_GENOME are dropped
+    pub fn from_genome(config: Settings, mut genome: Vec<Instruction>) -> GilesGenotype {
+        genome.truncate(MAX_GENOME);
+        GilesGenotype::with_genome(config, genome, FastRng::new())
+    }
+
+    pub fn genome(&self) -> &[Instruction] {
+        &self.genome
+    }
+
+    pub fn reproduce(&mut self) -> Self {
+        self.energy -= self.config.creature_reproduction_energy;
+        let mut child = self.child();
+        child.mutate();
+        child
+    }
+
+    fn with_genome(config: Settings, genome: Vec<Instruction>, rng: FastRng) -> GilesGenotype {
         GilesGenotype {
+            mutation_rate: config.giles_mutation_rate,
             config,
+            rng,
             energy: 0,
             ip: 0,
-            a: 0,
-            // i: [0; 5],
-            breed_rate: 0,
-            mutation_rate: 0,
-            // genome: GilesGenotype::randomize(),
+            r: [0; REGISTERS],
+            sight: [0; 8],
+            heard: 0,
+            genome,
         }
     }
 
-    fn _randomize() -> [u16; _GENOME] {
-        let mut genome = [0; _GENOME];
-        for i in 0..genome.len() {
-            genome[i] = fastrand::u16(..);
+    // a copy of the genome with a fresh machine state
+    fn child(&self) -> Self {
+        GilesGenotype::with_genome(self.config.clone(), self.genome.clone(), self.rng.clone())
+    }
+
+    fn mutate(&mut self) {
+        let len = self.genome.len();
+        for instruction in self.genome.iter_mut() {
+            if self.rng.f32() < self.mutation_rate {
+                *instruction = Instruction::random(&self.rng, len);
+            }
         }
-        genome
-    }
-
-    // totally dummy instruction set for now
-    fn _tick(&mut self) {
-        self.ip = (self.ip + 1) % (_GENOME as u16);
-        // let instruction = self.genome[self.ip as usize];
-        // match instruction {
-        //     // TODO this is just placeholder
-        //     // 0 => self.a = self.i[0],
-        //     1 => self.a = self.breed_rate as u16,
-        //     2 => self.a = self.mutation_rate as u16,
-        //     _ => (),
-        // }
+    }
+
+    fn reg(&mut self, r: u8) -> &mut i16 {
+        &mut self.r[r as usize % REGISTERS]
+    }
+
+    fn get(&self, r: u8) -> i16 {
+        self.r[r as usize % REGISTERS]
+    }
+
+    fn jump(&mut self, address: u16) {
+        self.ip = (address as usize % self.genome.len()) as u16;
+    }
+
+    fn execute(&mut self, instruction: Instruction) -> GenotypeActions {
+        match instruction {
+            Instruction::Nop => {}
+            Instruction::Set(r, value) => *self.reg(r) = value,
+            Instruction::Mov(r, s) => *self.reg(r) = self.get(s),
+            Instruction::Add(r, s) => *self.reg(r) = self.get(r).wrapping_add(self.get(s)),
+            Instruction::Sub(r, s) => *self.reg(r) = self.get(r).wrapping_sub(self.get(s)),
+            Instruction::Inc(r) => *self.reg(r) = self.get(r).wrapping_add(1),
+            Instruction::Dec(r) => *self.reg(r) = self.get(r).wrapping_sub(1),
+            Instruction::Jmp(address) => self.jump(address),
+            Instruction::Jz(r, address) => {
+                if self.get(r) == 0 {
+                    self.jump(address)
+                }
+            }
+            Instruction::Jnz(r, address) => {
+                if self.get(r) != 0 {
+                    self.jump(address)
+                }
+            }
+            Instruction::Look => return GenotypeActions::Look,
+            Instruction::See(r, s) => {
+                *self.reg(r) = self.sight[self.get(s).rem_euclid(8) as usize];
+            }
+            Instruction::Move(r) => {
+                let direction = int_to_dir(self.get(r).rem_euclid(8) as i32);
+                return GenotypeActions::Move(direction);
+            }
+            Instruction::Energy(r) => {
+                *self.reg(r) = (self.energy / ENERGY_SCALE).clamp(0, i16::MAX as i32) as i16;
+            }
+            Instruction::Rand(r) => *self.reg(r) = self.rng.i16(0..256),
+            Instruction::Breed => {
+                if self.energy >= self.config.creature_reproduction_energy {
+                    return GenotypeActions::Reproduce(Box::new(self.reproduce()));
+                }
+            }
+            Instruction::Mate => return GenotypeActions::Mate,
+            Instruction::Signal(r) => return GenotypeActions::Signal(self.get(r) as u8),
+            Instruction::Hear(r) => {
+                *self.reg(r) = self.heard;
+                self.heard = 0;
+            }
+        }
+        GenotypeActions::None
     }
 }
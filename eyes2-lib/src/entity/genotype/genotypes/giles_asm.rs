@@ -0,0 +1,251 @@
+//! Assembler and disassembler for the Giles genotype's instruction set.
+//!
+//! The assembly format has one instruction per line. A line may start with
+//! a label followed by a colon and anything after a semicolon is a comment.
+//! Registers are written r0 to r7 and jump targets are labels or addresses.
+//!
+//! ```text
+//! ; walk forwards until there is something in the way
+//! start:  look
+//!         see r1, r0
+//!         jnz r1, turn
+//!         move r0
+//!         jmp start
+//! turn:   inc r0
+//!         jmp start
+//! ```
+//!
+use super::giles::{GilesGenotype, Instruction, MAX_GENOME, REGISTERS};
+use super::{partner_as, Genotype};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum AsmError {
+    // (line number, mnemonic)
+    UnknownMnemonic(usize, String),
+    // (line number, the operands)
+    BadOperands(usize, String),
+    // (line number, label)
+    UnknownLabel(usize, String),
+    // (line number, label)
+    DuplicateLabel(usize, String),
+    // the number of instructions
+    TooLong(usize),
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AsmError::UnknownMnemonic(line, m) => {
+                write!(f, "line {}: unknown mnemonic '{}'", line, m)
+            }
+            AsmError::BadOperands(line, o) => write!(f, "line {}: bad operands '{}'", line, o),
+            AsmError::UnknownLabel(line, l) => write!(f, "line {}: unknown label '{}'", line, l),
+            AsmError::DuplicateLabel(line, l) => {
+                write!(f, "line {}: label '{}' is already defined", line, l)
+            }
+            AsmError::TooLong(len) => write!(
+                f,
+                "{} instructions is more than the limit of {}",
+                len, MAX_GENOME
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+// the kinds of operand each instruction takes
+#[derive(Clone, Copy)]
+enum Operand {
+    Register,
+    Value,
+    Address,
+}
+
+fn operands(mnemonic: &str) -> Option<&'static [Operand]> {
+    use Operand::*;
+    let operands: &'static [Operand] = match mnemonic {
+        "nop" | "look" | "breed" | "mate" => &[],
+        "set" => &[Register, Value],
+        "mov" | "add" | "sub" | "see" => &[Register, Register],
+        "inc" | "dec" | "move" | "energy" | "rand" | "signal" | "hear" => &[Register],
+        "jmp" => &[Address],
+        "jz" | "jnz" => &[Register, Address],
+        _ => return None,
+    };
+    Some(operands)
+}
+
+// split an instruction into its mnemonic and its operand values
+fn fields(instruction: &Instruction) -> (&'static str, Vec<i32>) {
+    match *instruction {
+        Instruction::Nop => ("nop", vec![]),
+        Instruction::Set(r, v) => ("set", vec![r as i32, v as i32]),
+        Instruction::Mov(r, s) => ("mov", vec![r as i32, s as i32]),
+        Instruction::Add(r, s) => ("add", vec![r as i32, s as i32]),
+        Instruction::Sub(r, s) => ("sub", vec![r as i32, s as i32]),
+        Instruction::Inc(r) => ("inc", vec![r as i32]),
+        Instruction::Dec(r) => ("dec", vec![r as i32]),
+        Instruction::Jmp(a) => ("jmp", vec![a as i32]),
+        Instruction::Jz(r, a) => ("jz", vec![r as i32, a as i32]),
+        Instruction::Jnz(r, a) => ("jnz", vec![r as i32, a as i32]),
+        Instruction::Look => ("look", vec![]),
+        Instruction::See(r, s) => ("see", vec![r as i32, s as i32]),
+        Instruction::Move(r) => ("move", vec![r as i32]),
+        Instruction::Energy(r) => ("energy", vec![r as i32]),
+        Instruction::Rand(r) => ("rand", vec![r as i32]),
+        Instruction::Breed => ("breed", vec![]),
+        Instruction::Mate => ("mate", vec![]),
+        Instruction::Signal(r) => ("signal", vec![r as i32]),
+        Instruction::Hear(r) => ("hear", vec![r as i32]),
+    }
+}
+
+// build an instruction from its mnemonic and operand values
+fn build(mnemonic: &str, v: &[i32]) -> Instruction {
+    let r = |i: usize| v[i] as u8;
+    let a = |i: usize| v[i] as u16;
+    match mnemonic {
+        "set" => Instruction::Set(r(0), v[1] as i16),
+        "mov" => Instruction::Mov(r(0), r(1)),
+        "add" => Instruction::Add(r(0), r(1)),
+        "sub" => Instruction::Sub(r(0), r(1)),
+        "inc" => Instruction::Inc(r(0)),
+        "dec" => Instruction::Dec(r(0)),
+        "jmp" => Instruction::Jmp(a(0)),
+        "jz" => Instruction::Jz(r(0), a(1)),
+        "jnz" => Instruction::Jnz(r(0), a(1)),
+        "look" => Instruction::Look,
+        "see" => Instruction::See(r(0), r(1)),
+        "move" => Instruction::Move(r(0)),
+        "energy" => Instruction::Energy(r(0)),
+        "rand" => Instruction::Rand(r(0)),
+        "breed" => Instruction::Breed,
+        "mate" => Instruction::Mate,
+        "signal" => Instruction::Signal(r(0)),
+        "hear" => Instruction::Hear(r(0)),
+        _ => Instruction::Nop,
+    }
+}
+
+fn label(address: i32) -> String {
+    format!("L{}", address)
+}
+
+// convert a genome to assembly text, jump targets are given labels and
+// targets beyond the end of the genome are left as addresses
+pub fn disassemble(genome: &[Instruction]) -> String {
+    let len = genome.len() as i32;
+    let targets: HashSet<i32> = genome
+        .iter()
+        .filter_map(|instruction| match instruction {
+            Instruction::Jmp(a) | Instruction::Jz(_, a) | Instruction::Jnz(_, a) => Some(*a as i32),
+            _ => None,
+        })
+        .filter(|a| *a < len)
+        .collect();
+
+    let mut text = String::new();
+    for (address, instruction) in genome.iter().enumerate() {
+        let (mnemonic, values) = fields(instruction);
+        let kinds = operands(mnemonic).unwrap();
+        let operands: Vec<String> = kinds
+            .iter()
+            .zip(values)
+            .map(|(kind, value)| match kind {
+                Operand::Register => format!("r{}", value),
+                Operand::Value => value.to_string(),
+                Operand::Address if value < len => label(value),
+                Operand::Address => value.to_string(),
+            })
+            .collect();
+
+        let address = address as i32;
+        let prefix = match targets.contains(&address) {
+            true => format!("{}:", label(address)),
+            false => String::new(),
+        };
+        let line = format!("{:<8}{} {}", prefix, mnemonic, operands.join(", "));
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+// the disassembly of a genotype if it is a Giles genotype
+pub fn disassemble_genotype(genotype: &dyn Genotype) -> Option<String> {
+    partner_as::<GilesGenotype>(genotype).map(|giles| disassemble(giles.genome()))
+}
+
+// convert assembly text to a genome
+pub fn assemble(source: &str) -> Result<Vec<Instruction>, AsmError> {
+    // the first pass finds the address of each label
+    let mut labels: HashMap<String, i32> = HashMap::new();
+    let mut lines: Vec<(usize, &str)> = Vec::new();
+    for (number, line) in source.lines().enumerate() {
+        let mut code = line.split(';').next().unwrap().trim();
+        if let Some((name, rest)) = code.split_once(':') {
+            let name = name.trim().to_string();
+            if labels.contains_key(&name) {
+                return Err(AsmError::DuplicateLabel(number + 1, name));
+            }
+            labels.insert(name, lines.len() as i32);
+            code = rest.trim();
+        }
+        if !code.is_empty() {
+            lines.push((number + 1, code));
+        }
+    }
+
+    if lines.len() > MAX_GENOME {
+        return Err(AsmError::TooLong(lines.len()));
+    }
+
+    // the second pass parses each instruction
+    let mut genome = Vec::new();
+    for (number, code) in lines {
+        let (mnemonic, rest) = code.split_once(char::is_whitespace).unwrap_or((code, ""));
+        let mnemonic = mnemonic.to_lowercase();
+        let kinds = operands(&mnemonic)
+            .ok_or_else(|| AsmError::UnknownMnemonic(number, mnemonic.clone()))?;
+        let bad = || AsmError::BadOperands(number, rest.trim().to_string());
+
+        let words: Vec<&str> = rest
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|w| !w.is_empty())
+            .collect();
+        if words.len() != kinds.len() {
+            return Err(bad());
+        }
+
+        let mut values = Vec::new();
+        for (kind, word) in kinds.iter().zip(words) {
+            let value = match kind {
+                Operand::Register => match word.strip_prefix(['r', 'R']) {
+                    Some(n) => n
+                        .parse::<i32>()
+                        .ok()
+                        .filter(|n| (0..REGISTERS as i32).contains(n)),
+                    None => None,
+                }
+                .ok_or_else(bad)?,
+                Operand::Value => word.parse::<i16>().map_err(|_| bad())? as i32,
+                Operand::Address => match word.parse::<u16>() {
+                    Ok(address) => address as i32,
+                    Err(_) => *labels
+                        .get(word)
+                        .ok_or_else(|| AsmError::UnknownLabel(number, word.to_string()))?,
+                },
+            };
+            values.push(value);
+        }
+        genome.push(build(&mnemonic, &values));
+    }
+    Ok(genome)
+}
+
+#[path = "giles_asm_test.rs"]
+#[cfg(test)]
+mod test;
@@ -0,0 +1,86 @@
+use super::*;
+use crate::Settings;
+use fastrand::Rng as FastRng;
+
+#[test]
+fn check_assemble_with_labels() {
+    let source = "
+        ; walk forwards until there is something in the way
+        start:  look
+                see r1, r0
+                jnz r1, turn
+                move r0
+                jmp start      ; and again
+        turn:   inc r0
+                jmp 0
+    ";
+    let genome = assemble(source).unwrap();
+    assert_eq!(
+        genome,
+        vec![
+            Instruction::Look,
+            Instruction::See(1, 0),
+            Instruction::Jnz(1, 5),
+            Instruction::Move(0),
+            Instruction::Jmp(0),
+            Instruction::Inc(0),
+            Instruction::Jmp(0),
+        ]
+    );
+}
+
+#[test]
+fn check_disassemble_roundtrip() {
+    let rng = FastRng::new();
+    let genome: Vec<Instruction> = (0..200).map(|_| Instruction::random(&rng, 200)).collect();
+    let text = disassemble(&genome);
+    assert_eq!(assemble(&text).unwrap(), genome);
+
+    let giles = GilesGenotype::from_genome(Settings::default(), genome);
+    assert_eq!(disassemble_genotype(&giles), Some(text));
+}
+
+#[test]
+fn check_assemble_errors() {
+    assert_eq!(
+        assemble("nop\nfly r1"),
+        Err(AsmError::UnknownMnemonic(2, "fly".to_string()))
+    );
+    assert_eq!(
+        assemble("set r9, 1"),
+        Err(AsmError::BadOperands(1, "r9, 1".to_string()))
+    );
+    assert_eq!(
+        assemble("move"),
+        Err(AsmError::BadOperands(1, "".to_string()))
+    );
+    assert_eq!(
+        assemble("jmp nowhere"),
+        Err(AsmError::UnknownLabel(1, "nowhere".to_string()))
+    );
+}
+
+#[test]
+fn check_disassemble_out_of_range_jumps() {
+    let genome = vec![Instruction::Jmp(1), Instruction::Jnz(2, 300)];
+    let text = disassemble(&genome);
+    assert_eq!(text, "        jmp L1\nL1:     jnz r2, 300\n");
+    assert_eq!(assemble(&text).unwrap(), genome);
+}
+
+#[test]
+fn check_genome_length_is_limited() {
+    assert_eq!(
+        assemble("a: nop\na: nop"),
+        Err(AsmError::DuplicateLabel(2, "a".to_string()))
+    );
+    let source = "jmp 65535\n".repeat(MAX_GENOME + 1);
+    assert_eq!(assemble(&source), Err(AsmError::TooLong(MAX_GENOME + 1)));
+
+    let genome = vec![Instruction::Jmp(u16::MAX); MAX_GENOME + 1];
+    let mut giles = GilesGenotype::from_genome(Settings::default(), genome);
+    assert_eq!(giles.genome().len(), MAX_GENOME);
+    for _ in 0..3 {
+        giles.tick();
+    }
+}
@@ -1,9 +1,9 @@
 //! Implement the Looker genotype, which is a creature that moves in one direction
 //! but turns when it sees food to the left or right.
 
-use super::{Genotype, GenotypeActions};
+use super::{hash_genome, Genotype, GenotypeActions, GenotypeInfo};
 use crate::{
-    entity::{get_vision_in_direction, Vision},
+    entity::{get_vision_in_direction, Scent, Vision},
     Cell, Settings,
 };
 use direction::Direction;
@@ -61,17 +61,19 @@ impl Genotype for LookerGenotype {
         GenotypeActions::None
     }
 
-    fn vision(&mut self, vision: Vision) {
+    fn vision(&mut self, vision: Vision, _scent: Scent) {
         match get_vision_in_direction(vision, &self.direction) {
             // grass ahead, keep going immediately
-            Cell::Grass => self.next_tick = 0,
+            Cell::Grass(_, _) => self.next_tick = 0,
             // obstacle ahead, turn around
-            Cell::Wall | Cell::Entity(_, _) => self.direction = self.direction.opposite(),
+            Cell::Wall | Cell::Entity(_, _) | Cell::Corpse(_) => {
+                self.direction = self.direction.opposite()
+            }
             // otherwise, look for grass to the left or right
             Cell::Empty => {
                 for turn in [&self.direction.left90(), &self.direction.right90()].iter() {
-                    if let Cell::Grass = get_vision_in_direction(vision, turn) {
-                        self.direction = *turn.clone();
+                    if let Cell::Grass(_, _) = get_vision_in_direction(vision, turn) {
+                        self.direction = **turn;
                         self.next_tick = 0;
                     }
                 }
@@ -83,9 +85,32 @@ impl Genotype for LookerGenotype {
         self.energy = energy;
     }
 
+    fn set_config(&mut self, config: Settings) {
+        self.config = config;
+    }
+
     fn get_sigil(&self) -> char {
         'L'
     }
+
+    fn describe(&self) -> GenotypeInfo {
+        GenotypeInfo {
+            params: vec![
+                (
+                    "ticks_per_move".to_string(),
+                    self.ticks_per_move.to_string(),
+                ),
+                (
+                    "reproduction_scale".to_string(),
+                    self.reproduction_scale.to_string(),
+                ),
+            ],
+            genome_size: 2,
+            summary: format!("moves every {} ticks", self.ticks_per_move),
+            genome_hash: hash_genome(&(self.ticks_per_move, self.reproduction_scale)),
+            ..GenotypeInfo::new(self.typetag_name())
+        }
+    }
 }
 
 impl LookerGenotype {
@@ -1,7 +1,15 @@
+pub mod fsm;
 pub mod giles;
+pub mod giles_asm;
 pub mod looker;
+pub mod neat;
+pub mod neural;
 pub mod noop;
 pub mod random;
 
+use super::genotype::partner_as;
+use super::genotype::DebugState;
+use super::genotype::Diet;
 use super::genotype::Genotype;
 use super::genotype::GenotypeActions;
+use super::genotype::{hash_genome, GenotypeInfo};
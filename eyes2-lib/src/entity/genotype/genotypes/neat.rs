@@ -0,0 +1,405 @@
+//! Implement the NEAT genotype, a neural network whose topology evolves.
+//!
+//! This follows NeuroEvolution of Augmenting Topologies: the genome is a list
+//! of connection genes, each tagged with an innovation number. New genomes
+//! connect every input directly to every output. On reproduction the child
+//! may mutate its weights, add a connection between two unconnected nodes or
+//! add a node by splitting an existing connection.
+//!
+//! Innovation numbers are shared between all NEAT creatures so that the same
+//! structural mutation in two lineages gets the same number.
+//!
+//! The inputs and outputs are the same as the fixed topology Neural genotype.
+
+use super::neural::{
+    choose, inputs, see, to_action, INPUTS, OUTPUTS, REPRODUCE, SIGHT, THINK_TICKS,
+};
+use super::{hash_genome, partner_as, Genotype, GenotypeActions, GenotypeInfo};
+use crate::entity::{Scent, Vision};
+use crate::Settings;
+use fastrand::Rng as FastRng;
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+
+// node ids: inputs come first, then outputs, then hidden nodes
+const FIRST_OUTPUT: u32 = INPUTS as u32;
+const FIRST_HIDDEN: u32 = (INPUTS + OUTPUTS) as u32;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ConnectionGene {
+    pub innovation: u32,
+    pub from: u32,
+    pub to: u32,
+    pub weight: f32,
+    pub enabled: bool,
+}
+
+// the register of structural mutations seen so far
+struct Innovations {
+    next_innovation: u32,
+    next_node: u32,
+    // innovation number of each connection between two nodes
+    connections: HashMap<(u32, u32), u32>,
+    // the node created by splitting each connection innovation
+    splits: HashMap<u32, u32>,
+}
+
+impl Innovations {
+    // the innovation number for a connection, new connections get a new number
+    fn connection(&mut self, from: u32, to: u32) -> u32 {
+        let next = &mut self.next_innovation;
+        *self.connections.entry((from, to)).or_insert_with(|| {
+            *next += 1;
+            *next - 1
+        })
+    }
+
+    // the id of the node made by splitting a connection
+    fn split(&mut self, innovation: u32) -> u32 {
+        let next = &mut self.next_node;
+        *self.splits.entry(innovation).or_insert_with(|| {
+            *next += 1;
+            *next - 1
+        })
+    }
+}
+
+static INNOVATIONS: LazyLock<Mutex<Innovations>> = LazyLock::new(|| {
+    Mutex::new(Innovations {
+        // the initial input to output connections use the first numbers
+        next_innovation: (INPUTS * OUTPUTS) as u32,
+        next_node: FIRST_HIDDEN,
+        connections: HashMap::new(),
+        splits: HashMap::new(),
+    })
+});
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct NeatGenotype {
+    #[serde(skip)]
+    config: Settings,
+    energy: i32,
+    #[serde(skip)]
+    rng: FastRng,
+    // hidden node ids in evaluation order, connections between hidden
+    // nodes always go from earlier to later in this list
+    hidden: Vec<u32>,
+    // the genome
+    #[serde(deserialize_with = "connections")]
+    connections: Vec<ConnectionGene>,
+    // the food and obstacle inputs from the last vision
+    sight: Vec<f32>,
+    // set when the creature has eaten since the last decision
+    fed: bool,
+    next_tick: u64,
+}
+
+// Genomes saved before the mate output was added number their nodes
+// differently, the initial connection genes show which numbering was used.
+fn connections<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<ConnectionGene>, D::Error> {
+    let connections = Vec::<ConnectionGene>::deserialize(deserializer)?;
+    let initial = (INPUTS * OUTPUTS) as u32;
+    let outdated = connections.iter().any(|gene| {
+        gene.innovation < initial
+            && (gene.from != gene.innovation / OUTPUTS as u32
+                || gene.to != FIRST_OUTPUT + gene.innovation % OUTPUTS as u32)
+    });
+    match outdated {
+        false => Ok(connections),
+        true => Err(de::Error::custom(
+            "neat genome was saved before the mate output was added, start a new world",
+        )),
+    }
+}
+
+#[typetag::serde(name = "neat_genotype")]
+impl Genotype for NeatGenotype {
+    fn tick(&mut self) -> GenotypeActions {
+        if self.next_tick > 0 {
+            self.next_tick -= 1;
+            return GenotypeActions::None;
+        }
+        self.next_tick = THINK_TICKS;
+
+        let outputs = self.think();
+        self.fed = false;
+        match choose(&self.rng, &outputs) {
+            REPRODUCE if self.energy >= self.config.creature_reproduction_energy => {
+                GenotypeActions::Reproduce(Box::new(self.reproduce()))
+            }
+            action => to_action(action),
+        }
+    }
+
+    fn set_energy(&mut self, energy: i32) {
+        self.energy = energy;
+    }
+
+    fn ate(&mut self, _energy: i32) {
+        self.fed = true;
+    }
+
+    fn set_config(&mut self, config: Settings) {
+        self.config = config;
+    }
+
+    fn get_sigil(&self) -> char {
+        'T'
+    }
+
+    fn vision(&mut self, vision: Vision, _scent: Scent) {
+        see(&vision, &mut self.sight);
+    }
+
+    // genes with the same innovation number in both parents are inherited
+    // from either at random, the other genes come from this parent
+    fn crossover(&self, partner: &dyn Genotype) -> Option<Box<dyn Genotype>> {
+        let partner = partner_as::<NeatGenotype>(partner)?;
+        let theirs: HashMap<u32, &ConnectionGene> = partner
+            .connections
+            .iter()
+            .map(|c| (c.innovation, c))
+            .collect();
+
+        let mut child = self.clone();
+        for connection in child.connections.iter_mut() {
+            if let Some(their_connection) = theirs.get(&connection.innovation) {
+                if self.rng.bool() {
+                    connection.weight = their_connection.weight;
+                    connection.enabled = their_connection.enabled;
+                }
+            }
+        }
+        child.energy = 0;
+        child.mutate();
+        Some(Box::new(child))
+    }
+
+    // the NEAT compatibility distance: the fraction of genes that are not
+    // shared plus the mean weight difference of the shared genes
+    fn distance(&self, other: &dyn Genotype) -> Option<f32> {
+        let other = partner_as::<NeatGenotype>(other)?;
+        let theirs: HashMap<u32, f32> = other
+            .connections
+            .iter()
+            .map(|c| (c.innovation, c.weight))
+            .collect();
+        let mut shared = 0;
+        let mut weight_difference = 0.0;
+        for connection in self.connections.iter() {
+            if let Some(weight) = theirs.get(&connection.innovation) {
+                shared += 1;
+                weight_difference += (connection.weight - weight).abs();
+            }
+        }
+        let genes = self.connections.len().max(other.connections.len()).max(1);
+        let unshared = self.connections.len() + other.connections.len() - 2 * shared;
+        Some(unshared as f32 / genes as f32 + weight_difference / shared.max(1) as f32)
+    }
+
+    fn describe(&self) -> GenotypeInfo {
+        let enabled = self.connections.iter().filter(|c| c.enabled).count();
+        let genes: Vec<(u32, u32, u32, u32, bool)> = self
+            .connections
+            .iter()
+            .map(|c| (c.innovation, c.from, c.to, c.weight.to_bits(), c.enabled))
+            .collect();
+        GenotypeInfo {
+            params: vec![
+                ("hidden".to_string(), self.hidden.len().to_string()),
+                ("enabled".to_string(), enabled.to_string()),
+            ],
+            genome_size: self.connections.len(),
+            summary: format!(
+                "{} hidden nodes, {} of {} connections enabled",
+                self.hidden.len(),
+                enabled,
+                self.connections.len()
+            ),
+            genome_hash: hash_genome(&(&self.hidden, genes)),
+            ..GenotypeInfo::new(self.typetag_name())
+        }
+    }
+}
+
+impl NeatGenotype {
+    pub fn new(config: Settings) -> NeatGenotype {
+        let rng = FastRng::new();
+        let mut connections = Vec::new();
+        for from in 0..INPUTS as u32 {
+            for output in 0..OUTPUTS as u32 {
+                connections.push(ConnectionGene {
+                    innovation: from * OUTPUTS as u32 + output,
+                    from,
+                    to: FIRST_OUTPUT + output,
+                    weight: rng.f32() * 2.0 - 1.0,
+                    enabled: true,
+                });
+            }
+        }
+
+        NeatGenotype {
+            config,
+            energy: 0,
+            rng,
+            hidden: Vec::new(),
+            connections,
+            sight: vec![0.0; SIGHT],
+            fed: false,
+            next_tick: THINK_TICKS,
+        }
+    }
+
+    pub fn reproduce(&mut self) -> Self {
+        self.energy -= self.config.creature_reproduction_energy;
+        let mut child = self.clone();
+        child.mutate();
+        child
+    }
+
+    // feed the inputs forward through the network
+    fn think(&self) -> Vec<f32> {
+        let mut values: HashMap<u32, f32> = HashMap::new();
+        for (id, value) in inputs(&self.sight, self.energy, self.fed, &self.config)
+            .into_iter()
+            .enumerate()
+        {
+            values.insert(id as u32, value);
+        }
+
+        let sum_into = |node: u32, values: &HashMap<u32, f32>| -> f32 {
+            self.connections
+                .iter()
+                .filter(|c| c.enabled && c.to == node)
+                .map(|c| c.weight * values.get(&c.from).unwrap_or(&0.0))
+                .sum()
+        };
+
+        for node in self.hidden.iter() {
+            let value = sum_into(*node, &values).tanh();
+            values.insert(*node, value);
+        }
+        (0..OUTPUTS as u32)
+            .map(|output| sum_into(FIRST_OUTPUT + output, &values))
+            .collect()
+    }
+
+    fn mutate(&mut self) {
+        let config = &self.config;
+        for connection in self.connections.iter_mut() {
+            if self.rng.f32() < config.neat_weight_rate {
+                connection.weight += (self.rng.f32() * 2.0 - 1.0) * config.neat_weight_size;
+            }
+        }
+
+        let mut innovations = INNOVATIONS.lock().unwrap();
+        // make sure new numbers do not clash with a genome loaded from a file
+        for connection in self.connections.iter() {
+            innovations.next_innovation =
+                innovations.next_innovation.max(connection.innovation + 1);
+        }
+        for node in self.hidden.iter() {
+            innovations.next_node = innovations.next_node.max(node + 1);
+        }
+
+        if self.rng.f32() < self.config.neat_add_connection {
+            self.add_connection(&mut innovations);
+        }
+        if self.rng.f32() < self.config.neat_add_node {
+            self.add_node(&mut innovations);
+        }
+    }
+
+    // position of a node in evaluation order: inputs, hidden nodes, outputs
+    fn order(&self, node: u32) -> usize {
+        if node < FIRST_OUTPUT {
+            0
+        } else if node < FIRST_HIDDEN {
+            usize::MAX
+        } else {
+            1 + self.hidden.iter().position(|h| *h == node).unwrap()
+        }
+    }
+
+    fn add_connection(&mut self, innovations: &mut Innovations) {
+        // choose a source from the inputs and hidden nodes and a target from
+        // the hidden nodes and outputs
+        let sources = INPUTS + self.hidden.len();
+        let targets = self.hidden.len() + OUTPUTS;
+        let from = match self.rng.usize(0..sources) {
+            i if i < INPUTS => i as u32,
+            i => self.hidden[i - INPUTS],
+        };
+        let to = match self.rng.usize(0..targets) {
+            i if i < self.hidden.len() => self.hidden[i],
+            i => FIRST_OUTPUT + (i - self.hidden.len()) as u32,
+        };
+
+        // keep the network feed forward and skip existing connections
+        if self.order(from) >= self.order(to)
+            || self
+                .connections
+                .iter()
+                .any(|c| c.from == from && c.to == to)
+        {
+            return;
+        }
+        self.connections.push(ConnectionGene {
+            innovation: innovations.connection(from, to),
+            from,
+            to,
+            weight: self.rng.f32() * 2.0 - 1.0,
+            enabled: true,
+        });
+    }
+
+    fn add_node(&mut self, innovations: &mut Innovations) {
+        let enabled: Vec<usize> = (0..self.connections.len())
+            .filter(|i| self.connections[*i].enabled)
+            .collect();
+        if enabled.is_empty() {
+            return;
+        }
+        let split = enabled[self.rng.usize(0..enabled.len())];
+        let ConnectionGene {
+            innovation,
+            from,
+            to,
+            weight,
+            ..
+        } = self.connections[split].clone();
+
+        let node = innovations.split(innovation);
+        if self.hidden.contains(&node) {
+            return;
+        }
+        self.connections[split].enabled = false;
+
+        // the new node goes just before the target so it is evaluated in order
+        let position = match self.order(to) {
+            usize::MAX => self.hidden.len(),
+            order => order - 1,
+        };
+        self.hidden.insert(position, node);
+
+        // the incoming connection has weight 1 and the outgoing connection
+        // keeps the old weight so that the behaviour barely changes
+        for (from, to, weight) in [(from, node, 1.0), (node, to, weight)] {
+            self.connections.push(ConnectionGene {
+                innovation: innovations.connection(from, to),
+                from,
+                to,
+                weight,
+                enabled: true,
+            });
+        }
+    }
+}
+
+#[path = "neat_test.rs"]
+#[cfg(test)]
+mod test;
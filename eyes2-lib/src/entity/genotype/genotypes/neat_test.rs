@@ -0,0 +1,70 @@
+use super::*;
+
+fn get_config() -> Settings {
+    Settings {
+        neat_weight_rate: 0.5,
+        neat_add_connection: 0.5,
+        neat_add_node: 0.5,
+        ..Settings::default()
+    }
+}
+
+#[test]
+fn check_mutations_stay_feed_forward() {
+    let mut genotype = NeatGenotype::new(get_config());
+    for _ in 0..200 {
+        genotype.mutate();
+    }
+
+    assert!(!genotype.hidden.is_empty());
+    for connection in genotype.connections.iter() {
+        assert!(genotype.order(connection.from) < genotype.order(connection.to));
+    }
+    let outputs = genotype.think();
+    assert_eq!(outputs.len(), OUTPUTS);
+    assert!(outputs.iter().all(|o| o.is_finite()));
+}
+
+#[test]
+fn check_innovations_are_shared() {
+    let mut first = NeatGenotype::new(get_config());
+    let mut second = first.clone();
+    let mut innovations = INNOVATIONS.lock().unwrap();
+
+    // splitting the same connection in two lineages makes the same genes
+    first.connections.truncate(1);
+    second.connections.truncate(1);
+    first.add_node(&mut innovations);
+    second.add_node(&mut innovations);
+
+    assert_eq!(first.hidden, second.hidden);
+    assert_eq!(first.connections, second.connections);
+}
+
+#[test]
+fn check_genome_serializes() {
+    let mut genotype = NeatGenotype::new(get_config());
+    for _ in 0..20 {
+        genotype.mutate();
+    }
+    let boxed: Box<dyn Genotype> = Box::new(genotype.clone());
+
+    let yaml = serde_yaml::to_string(&boxed).unwrap();
+    let loaded: Box<dyn Genotype> = serde_yaml::from_str(&yaml).unwrap();
+
+    assert_eq!(serde_yaml::to_string(&loaded).unwrap(), yaml);
+}
+
+#[test]
+fn check_old_genomes_are_rejected() {
+    let mut genotype = NeatGenotype::new(get_config());
+
+    // before the mate output, gene 11 joined input 1 to output 0
+    let gene = &mut genotype.connections[OUTPUTS - 1];
+    gene.from = 1;
+    gene.to = FIRST_OUTPUT;
+    let yaml = serde_yaml::to_string(&genotype).unwrap();
+
+    let error = serde_yaml::from_str::<NeatGenotype>(&yaml).err().unwrap();
+    assert!(error.to_string().contains("mate output"));
+}
@@ -0,0 +1,301 @@
+//! Implement the Neural genotype, a small fixed topology neural network.
+//!
+//! The inputs are the cells from the last vision, the creature's energy and
+//! the result of its last action (whether it found food). A single hidden
+//! layer feeds one output per action and the action is chosen at random
+//! with the probabilities given by a softmax of the outputs.
+//!
+//! The weights are the genome, on reproduction each weight of the child
+//! may mutate.
+//!
+//! The encoding of the inputs and the choice of action are shared with
+//! the NEAT genotype.
+
+use super::{hash_genome, partner_as, Genotype, GenotypeActions, GenotypeInfo};
+use crate::entity::{Scent, Vision};
+use crate::utils::int_to_dir;
+use crate::{Cell, Settings};
+use fastrand::Rng as FastRng;
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize};
+
+// two inputs for each cell in the vision plus energy, last result and bias
+pub(super) const SIGHT: usize = 8 * 2;
+pub(super) const INPUTS: usize = SIGHT + 3;
+const HIDDEN: usize = 8;
+// move in each of 8 directions, look, reproduce, mate or do nothing
+pub(super) const OUTPUTS: usize = 12;
+const LOOK: usize = 8;
+pub(super) const REPRODUCE: usize = 9;
+pub(super) const MATE: usize = 10;
+// the network chooses an action once every THINK_TICKS
+pub(super) const THINK_TICKS: u64 = 100;
+// the energy of a typical plant, used to scale the food inputs
+const FOOD_SCALE: f32 = 1000.0;
+// the bias of the mate output given to genomes saved before it existed, far
+// below any other output so that they keep their behaviour until it mutates
+const OLD_MATE_BIAS: f32 = -20.0;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct NeuralGenotype {
+    #[serde(skip)]
+    config: Settings,
+    energy: i32,
+    #[serde(skip)]
+    rng: FastRng,
+    // weights from the inputs to each hidden node
+    #[serde(deserialize_with = "hidden_weights")]
+    hidden_weights: Vec<f32>,
+    // weights from the hidden nodes (plus a bias) to each output
+    #[serde(deserialize_with = "output_weights")]
+    output_weights: Vec<f32>,
+    // the food and obstacle inputs from the last vision
+    sight: Vec<f32>,
+    // set when the creature has eaten since the last decision
+    fed: bool,
+    next_tick: u64,
+}
+
+#[typetag::serde(name = "neural_genotype")]
+impl Genotype for NeuralGenotype {
+    fn tick(&mut self) -> GenotypeActions {
+        if self.next_tick > 0 {
+            self.next_tick -= 1;
+            return GenotypeActions::None;
+        }
+        self.next_tick = THINK_TICKS;
+
+        let outputs = self.think();
+        self.fed = false;
+        match choose(&self.rng, &outputs) {
+            REPRODUCE if self.energy >= self.config.creature_reproduction_energy => {
+                GenotypeActions::Reproduce(Box::new(self.reproduce()))
+            }
+            action => to_action(action),
+        }
+    }
+
+    fn set_energy(&mut self, energy: i32) {
+        self.energy = energy;
+    }
+
+    fn ate(&mut self, _energy: i32) {
+        self.fed = true;
+    }
+
+    fn set_config(&mut self, config: Settings) {
+        self.config = config;
+    }
+
+    fn get_sigil(&self) -> char {
+        'B'
+    }
+
+    fn vision(&mut self, vision: Vision, _scent: Scent) {
+        see(&vision, &mut self.sight);
+    }
+
+    // uniform crossover, each weight comes from either parent
+    fn crossover(&self, partner: &dyn Genotype) -> Option<Box<dyn Genotype>> {
+        let partner = partner_as::<NeuralGenotype>(partner)?;
+        let mut child = self.clone();
+        let theirs = partner
+            .hidden_weights
+            .iter()
+            .chain(partner.output_weights.iter());
+        for (weight, their_weight) in child
+            .hidden_weights
+            .iter_mut()
+            .chain(child.output_weights.iter_mut())
+            .zip(theirs)
+        {
+            if self.rng.bool() {
+                *weight = *their_weight;
+            }
+        }
+        child.energy = 0;
+        child.mutate();
+        Some(Box::new(child))
+    }
+
+    // the mean difference between the weights
+    fn distance(&self, other: &dyn Genotype) -> Option<f32> {
+        let other = partner_as::<NeuralGenotype>(other)?;
+        let mine = self.hidden_weights.iter().chain(self.output_weights.iter());
+        let theirs = other
+            .hidden_weights
+            .iter()
+            .chain(other.output_weights.iter());
+        let count = self.hidden_weights.len() + self.output_weights.len();
+        let total: f32 = mine.zip(theirs).map(|(a, b)| (a - b).abs()).sum();
+        Some(total / count.max(1) as f32)
+    }
+
+    fn describe(&self) -> GenotypeInfo {
+        let weights = || self.hidden_weights.iter().chain(self.output_weights.iter());
+        let bits: Vec<u32> = weights().map(|w| w.to_bits()).collect();
+        let mean = weights().map(|w| w.abs()).sum::<f32>() / bits.len() as f32;
+        GenotypeInfo {
+            params: vec![
+                ("inputs".to_string(), INPUTS.to_string()),
+                ("hidden".to_string(), HIDDEN.to_string()),
+                ("outputs".to_string(), OUTPUTS.to_string()),
+            ],
+            genome_size: bits.len(),
+            summary: format!(
+                "{}-{}-{} network, mean |weight| {:.2}",
+                INPUTS, HIDDEN, OUTPUTS, mean
+            ),
+            genome_hash: hash_genome(&bits),
+            ..GenotypeInfo::new(self.typetag_name())
+        }
+    }
+}
+
+impl NeuralGenotype {
+    pub fn new(config: Settings) -> NeuralGenotype {
+        let rng = FastRng::new();
+        let random_weights =
+            |count: usize| -> Vec<f32> { (0..count).map(|_| rng.f32() * 2.0 - 1.0).collect() };
+
+        NeuralGenotype {
+            config,
+            energy: 0,
+            hidden_weights: random_weights(INPUTS * HIDDEN),
+            output_weights: random_weights((HIDDEN + 1) * OUTPUTS),
+            rng,
+            sight: vec![0.0; SIGHT],
+            fed: false,
+            next_tick: THINK_TICKS,
+        }
+    }
+
+    pub fn reproduce(&mut self) -> Self {
+        self.energy -= self.config.creature_reproduction_energy;
+        let mut child = self.clone();
+        child.mutate();
+        child
+    }
+
+    // feed the inputs forward through the network
+    fn think(&self) -> [f32; OUTPUTS] {
+        let inputs = inputs(&self.sight, self.energy, self.fed, &self.config);
+
+        let mut hidden: Vec<f32> = self
+            .hidden_weights
+            .chunks(INPUTS)
+            .map(|weights| dot(weights, &inputs).tanh())
+            .collect();
+        hidden.push(1.0);
+
+        let mut outputs = [0.0; OUTPUTS];
+        for (output, weights) in outputs
+            .iter_mut()
+            .zip(self.output_weights.chunks(HIDDEN + 1))
+        {
+            *output = dot(weights, &hidden);
+        }
+        outputs
+    }
+
+    fn mutate(&mut self) {
+        let rate = self.config.neural_mutation_rate;
+        let size = self.config.neural_mutation_size;
+        let rng = &self.rng;
+        for weight in self
+            .hidden_weights
+            .iter_mut()
+            .chain(self.output_weights.iter_mut())
+        {
+            if rng.f32() < rate {
+                *weight += (rng.f32() * 2.0 - 1.0) * size;
+            }
+        }
+    }
+}
+
+fn hidden_weights<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<f32>, D::Error> {
+    let weights = Vec::<f32>::deserialize(deserializer)?;
+    match weights.len() == INPUTS * HIDDEN {
+        true => Ok(weights),
+        false => Err(de::Error::invalid_length(
+            weights.len(),
+            &"a neural genome with 19 inputs to 8 hidden nodes",
+        )),
+    }
+}
+
+// Genomes saved before the mate output was added have one output fewer,
+// they are given a mate output with no weights and a bias that it almost
+// never wins against so that they still load and still behave as before.
+fn output_weights<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<f32>, D::Error> {
+    let mut weights = Vec::<f32>::deserialize(deserializer)?;
+    let node = HIDDEN + 1;
+    if weights.len() == node * (OUTPUTS - 1) {
+        let mut mate = [0.0; HIDDEN + 1];
+        mate[HIDDEN] = OLD_MATE_BIAS;
+        weights.splice(MATE * node..MATE * node, mate);
+    }
+    match weights.len() == node * OUTPUTS {
+        true => Ok(weights),
+        false => Err(de::Error::invalid_length(
+            weights.len(),
+            &"a neural genome with 9 weights to each of 12 outputs",
+        )),
+    }
+}
+
+fn dot(weights: &[f32], values: &[f32]) -> f32 {
+    weights.iter().zip(values).map(|(w, v)| w * v).sum()
+}
+
+// encode the food and obstacles in each cell of a vision as inputs
+pub(super) fn see(vision: &Vision, sight: &mut [f32]) {
+    for (i, cell) in vision.iter().enumerate() {
+        let (food, obstacle) = match cell {
+            Cell::Grass(_, energy) => (*energy as f32 / FOOD_SCALE, 0.0),
+            Cell::Empty => (0.0, 0.0),
+            Cell::Entity(_, _) | Cell::Corpse(_) | Cell::Wall => (0.0, 1.0),
+        };
+        sight[i * 2] = food;
+        sight[i * 2 + 1] = obstacle;
+    }
+}
+
+// the full list of network inputs including the bias
+pub(super) fn inputs(sight: &[f32], energy: i32, fed: bool, config: &Settings) -> Vec<f32> {
+    let mut inputs = sight.to_vec();
+    inputs.push(energy as f32 / config.creature_reproduction_energy as f32);
+    inputs.push(if fed { 1.0 } else { 0.0 });
+    inputs.push(1.0);
+    inputs
+}
+
+// pick an output at random using the softmax of the outputs
+pub(super) fn choose(rng: &FastRng, outputs: &[f32]) -> usize {
+    let max = outputs.iter().cloned().fold(f32::MIN, f32::max);
+    let exps: Vec<f32> = outputs.iter().map(|o| (o - max).exp()).collect();
+    let mut pick = rng.f32() * exps.iter().sum::<f32>();
+    for (action, exp) in exps.iter().enumerate() {
+        if pick < *exp {
+            return action;
+        }
+        pick -= exp;
+    }
+    outputs.len() - 1
+}
+
+// the action for each output, reproduction is handled by the caller
+// because it needs to create the child genotype
+pub(super) fn to_action(output: usize) -> GenotypeActions {
+    match output {
+        action if action < LOOK => GenotypeActions::Move(int_to_dir(action as i32)),
+        LOOK => GenotypeActions::Look,
+        MATE => GenotypeActions::Mate,
+        _ => GenotypeActions::None,
+    }
+}
+
+#[path = "neural_test.rs"]
+#[cfg(test)]
+mod test;
@@ -0,0 +1,124 @@
+use super::*;
+
+fn get_config() -> Settings {
+    Settings {
+        neural_mutation_rate: 0.5,
+        neural_mutation_size: 0.5,
+        ..Settings::default()
+    }
+}
+
+fn weights(genotype: &NeuralGenotype) -> Vec<f32> {
+    let weights = genotype.hidden_weights.iter();
+    weights
+        .chain(genotype.output_weights.iter())
+        .cloned()
+        .collect()
+}
+
+#[test]
+fn check_mutations_change_some_weights() {
+    let mut genotype = NeuralGenotype::new(get_config());
+    let before = weights(&genotype);
+    genotype.mutate();
+    let after = weights(&genotype);
+
+    assert_eq!(after.len(), (INPUTS * HIDDEN) + (HIDDEN + 1) * OUTPUTS);
+    let changed = before.iter().zip(&after).filter(|(a, b)| a != b).count();
+    assert!(changed > 0 && changed < after.len());
+    assert!(before
+        .iter()
+        .zip(&after)
+        .all(|(a, b)| (a - b).abs() <= 0.5 + 1e-6));
+
+    // no mutations without a mutation rate
+    genotype.config.neural_mutation_rate = 0.0;
+    let child = genotype.reproduce();
+    assert_eq!(weights(&child), after);
+}
+
+#[test]
+fn check_crossover_takes_weights_from_both_parents() {
+    let config = Settings {
+        neural_mutation_rate: 0.0,
+        ..get_config()
+    };
+    let (first, second) = (
+        NeuralGenotype::new(config.clone()),
+        NeuralGenotype::new(config),
+    );
+    let child = first.crossover(&second).unwrap();
+    let child = partner_as::<NeuralGenotype>(child.as_ref()).unwrap();
+
+    let (mine, theirs) = (weights(&first), weights(&second));
+    let from_first = weights(child)
+        .iter()
+        .zip(&mine)
+        .filter(|(c, m)| c == m)
+        .count();
+    let from_second = weights(child)
+        .iter()
+        .zip(&theirs)
+        .filter(|(c, t)| c == t)
+        .count();
+    assert_eq!(from_first + from_second, mine.len());
+    assert!(from_first > 0 && from_second > 0);
+}
+
+#[test]
+fn check_fed_only_after_eating() {
+    let mut genotype = NeuralGenotype::new(get_config());
+    genotype.set_energy(500);
+    assert!(!genotype.fed);
+    genotype.ate(100);
+    assert!(genotype.fed);
+    assert_eq!(genotype.energy, 500);
+
+    // the flag is an input to the next decision only
+    genotype.next_tick = 0;
+    genotype.tick();
+    assert!(!genotype.fed);
+}
+
+#[test]
+fn check_genome_serializes() {
+    let boxed: Box<dyn Genotype> = Box::new(NeuralGenotype::new(get_config()));
+
+    let yaml = serde_yaml::to_string(&boxed).unwrap();
+    let loaded: Box<dyn Genotype> = serde_yaml::from_str(&yaml).unwrap();
+
+    assert_eq!(serde_yaml::to_string(&loaded).unwrap(), yaml);
+    assert_eq!(loaded.typetag_name(), "neural_genotype");
+}
+
+#[test]
+fn check_old_genomes_get_a_mate_output() {
+    let mut genotype = NeuralGenotype::new(get_config());
+    let node = HIDDEN + 1;
+    let old = genotype.output_weights.clone();
+    genotype
+        .output_weights
+        .drain(MATE * node..(MATE + 1) * node);
+
+    let yaml = serde_yaml::to_string(&genotype).unwrap();
+    let loaded: NeuralGenotype = serde_yaml::from_str(&yaml).unwrap();
+
+    assert_eq!(loaded.output_weights.len(), node * OUTPUTS);
+    assert!(loaded.output_weights[MATE * node..(MATE + 1) * node - 1]
+        .iter()
+        .all(|w| *w == 0.0));
+    assert_eq!(loaded.output_weights[..MATE * node], old[..MATE * node]);
+
+    // the new output is almost never chosen
+    let mut loaded = loaded;
+    loaded.set_config(get_config());
+    loaded.energy = 100;
+    let mates = (0..10_000)
+        .filter(|_| choose(&loaded.rng, &loaded.think()) == MATE)
+        .count();
+    assert!(mates < 10, "{} mates", mates);
+
+    genotype.output_weights.pop();
+    let yaml = serde_yaml::to_string(&genotype).unwrap();
+    assert!(serde_yaml::from_str::<NeuralGenotype>(&yaml).is_err());
+}
@@ -1,7 +1,9 @@
 //! Implement the Random genotype, which is a creature that moves randomly and reproduces
 //! when it has enough energy.
+//!
+//! The same genotype with a carnivorous diet makes a random scavenger.
 
-use super::{Genotype, GenotypeActions};
+use super::{Diet, Genotype, GenotypeActions, GenotypeInfo};
 use crate::utils::random_direction;
 use crate::Settings;
 use direction::Direction;
@@ -16,6 +18,8 @@ pub struct RandomGenotype {
     #[serde(skip)]
     rng: FastRng,
     direction: Direction,
+    #[serde(default)]
+    diet: Diet,
 }
 
 #[typetag::serde(name = "random_genotype")]
@@ -37,8 +41,31 @@ impl Genotype for RandomGenotype {
         self.energy = energy;
     }
 
+    fn set_config(&mut self, config: Settings) {
+        self.config = config;
+    }
+
     fn get_sigil(&self) -> char {
-        'R'
+        match self.diet {
+            Diet::Herbivore => 'R',
+            _ => 'S',
+        }
+    }
+
+    fn diet(&self) -> Diet {
+        self.diet
+    }
+
+    fn describe(&self) -> GenotypeInfo {
+        let name = match self.diet {
+            Diet::Herbivore => "random",
+            _ => "scavenger",
+        };
+        GenotypeInfo {
+            params: vec![("diet".to_string(), format!("{:?}", self.diet))],
+            summary: "moves at random".to_string(),
+            ..GenotypeInfo::new(name)
+        }
     }
 }
 
@@ -49,6 +76,14 @@ impl RandomGenotype {
             energy: 0,
             rng: FastRng::new(),
             direction: Direction::North,
+            diet: Diet::Herbivore,
+        }
+    }
+
+    pub fn scavenger(config: Settings) -> RandomGenotype {
+        RandomGenotype {
+            diet: Diet::Carnivore,
+            ..RandomGenotype::new(config)
         }
     }
 
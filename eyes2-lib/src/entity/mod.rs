@@ -3,7 +3,13 @@ mod genotype;
 pub mod update;
 pub mod vision;
 
-pub use self::creature::Creature;
-pub use self::genotype::genotype::{new_genotype, Genotype};
+pub use self::creature::{Creature, CreatureDebug};
+pub use self::genotype::genotype::{
+    new_genotype, DebugState, Diet, Genotype, GenotypeActions, GenotypeInfo,
+};
+pub use self::genotype::genotypes::giles::{GilesGenotype, Instruction};
+pub use self::genotype::genotypes::giles_asm::{
+    assemble, disassemble, disassemble_genotype, AsmError,
+};
 pub use self::update::{Update, UpdateQueue};
-pub use self::vision::{get_vision_in_direction, look_world, Vision};
+pub use self::vision::{get_vision_in_direction, look_world, Scent, Vision};
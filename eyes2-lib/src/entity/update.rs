@@ -9,8 +9,11 @@ pub type UpdateQueue = Vec<Update>;
 /// Represent the possible world update service requests that
 /// Creatures can place on the update queue.
 pub enum Update {
-    AddEntity(Creature),
+    AddEntity(Box<Creature>),
     MoveEntity(u64, Coord, Coord),
     RemoveEntity(u64, Coord),
     Look(u64),
+    Scent(Coord, f32),
+    Signal(u64, Coord, u8),
+    Mate(u64),
 }
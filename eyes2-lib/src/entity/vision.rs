@@ -7,16 +7,23 @@ use crate::{Cell, WorldGrid};
 
 pub type Vision = [Cell; 8];
 
+// the strength of the scent in each of the 8 adjacent cells, in the same
+// order as Vision
+pub type Scent = [f32; 8];
+
 // TODO I'd like to make these functions methods on the Vision type but
 // because its a native type I need something called an extension trait
 
-// create a vision array for the given coordinate in a world grid
-pub fn look_world(coord: Coord, grid: &WorldGrid) -> Vision {
+// create the vision and scent arrays for the given coordinate in a world
+// grid, a creature smells the cells that it looks at
+pub fn look_world(coord: Coord, grid: &WorldGrid) -> (Vision, Scent) {
     let mut vision = [Cell::Empty; 8];
+    let mut scent = [0.0; 8];
     for (i, direction) in Directions::into_iter(Directions).enumerate() {
         vision[i] = grid.get_cell(coord + direction.coord());
+        scent[i] = grid.get_scent(coord + direction.coord());
     }
-    vision
+    (vision, scent)
 }
 
 pub fn get_vision_in_direction(vision: Vision, direction: &Direction) -> Cell {
@@ -10,4 +10,4 @@ pub mod utils;
 
 // these are the public API structures
 pub use crate::settings::Settings;
-pub use crate::world::{save_world, Cell, World, WorldGrid};
+pub use crate::world::{load_world_file, save_stats, save_world, Cell, Plant, World, WorldGrid};
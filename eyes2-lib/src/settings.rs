@@ -1,13 +1,29 @@
+use crate::world::{GrassModel, Plant, RestartPolicy};
 use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+use std::error::Error;
+use std::fmt;
+
+// the fields that only take effect when a new world is created, changes to
+// any other field can be applied to a running world
+const RESET_FIELDS: [&str; 8] = [
+    "size",
+    "grass_count",
+    "creature_initial_energy",
+    "grass_model",
+    "fertile_zones",
+    "fertile_radius",
+    "creatures",
+    "plants",
+];
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
 pub struct Settings {
     // world size in cells (square)
     pub size: u16,
     // number of grass blocks to add to the world
     pub grass_count: u16,
-    // energy gained from eating grass
-    pub grass_energy: i32,
     // range of energy for new creatures
     pub creature_initial_energy: (i32, i32),
     // energy level for reproduction
@@ -16,10 +32,76 @@ pub struct Settings {
     pub creature_move_energy: i32,
     // energy gained from idling
     pub creature_idle_energy: i32,
+    // energy of the body a creature leaves when it dies, its corpse also
+    // holds any energy the creature had left (0 for no corpses)
+    pub corpse_energy: i32,
+    // energy a corpse loses to decay each tick
+    pub corpse_decay: i32,
+    // energy lost from leaving scent and the most scent a creature can
+    // leave at once
+    pub scent_energy: i32,
+    pub scent_max: f32,
+    // fraction of the scent in a cell that spreads to its neighbours and the
+    // fraction that evaporates on each scent tick
+    pub scent_diffusion: f32,   // MAX 1.0
+    pub scent_evaporation: f32, // MAX 1.0
+    // number of ticks between scent ticks
+    pub scent_interval: u64,
+    // energy lost from sending a signal and the distance in cells it carries
+    pub signal_energy: i32,
+    pub signal_radius: u16,
+    // allow creatures to mate with a compatible neighbour, both need at
+    // least mate_energy
+    pub mating: bool,
+    pub mate_energy: i32,
+    // every species_interval ticks the creatures are grouped into species,
+    // creatures whose genomes are within species_threshold of a species'
+    // representative join that species (interval 0 to disable)
+    pub species_interval: u64,
+    pub species_threshold: f32,
+    // every stats_interval ticks the population and its diversity are
+    // recorded (interval 0 to disable), the mean genome distance is measured
+    // on stats_sample creatures of each genotype and at most stats_history
+    // records are kept
+    pub stats_interval: u64,
+    pub stats_sample: usize,
+    pub stats_history: usize,
+    // number of ticks between samples of the grass and energy heatmaps
+    // (0 to disable)
+    pub heatmap_interval: u64,
+    // save a checkpoint of the world every autosave_ticks ticks and every
+    // autosave_minutes minutes (0 to disable either), the newest
+    // autosave_keep checkpoints are kept (see eyes2-lib/src/world/store.rs)
+    pub autosave_ticks: u64,
+    pub autosave_minutes: u64,
+    pub autosave_keep: usize,
+    // what happens when the creatures die out: fresh starts a new random
+    // world, reseed starts one with copies of the restart_keep best genomes
+    // seen and inject also adds copies whenever the population falls below
+    // restart_threshold (see eyes2-lib/src/world/restart.rs)
+    pub restart_policy: RestartPolicy,
+    pub restart_keep: usize,
+    pub restart_threshold: u64,
     // Speed of Creature movement chance of moving per tick
     // Only used for Random movement mode (not Genome based movement control)
     pub creature_move_rate: f32, // MAX 1.0
 
+    // the rule used to grow new grass: spread, random, logistic or fertile
+    // see eyes2-lib/src/world/vegetation.rs
+    pub grass_model: GrassModel,
+    // number of grass cells added per grass tick (random and fertile models)
+    pub grass_spawn: u16,
+    // chance per grass tick that an empty cell surrounded by grass regrows
+    // (logistic model)
+    pub grass_regrowth: f32, // MAX 1.0
+    // number of fertile zones and their radius in cells (fertile model)
+    pub fertile_zones: u16,
+    pub fertile_radius: u16,
+    // amplitude of the seasonal variation in grass growth (0.0 for no seasons)
+    pub season_amplitude: f32, // MAX 0.99
+    // length of a full cycle of seasons in ticks
+    pub season_length: u64,
+
     // these are initial values for settings that change during runtime
 
     // speed of the simulation
@@ -32,22 +114,107 @@ pub struct Settings {
     // for the list of valid genome types
     // TODO: the String should be an enum?
     pub creatures: Vec<(String, u16)>,
+
+    // chance that each weight of a neural genotype mutates on reproduction
+    // and the largest change a mutation can make
+    pub neural_mutation_rate: f32, // MAX 1.0
+    pub neural_mutation_size: f32,
+    // mutation settings for the NEAT genotype: the chance and size of a
+    // change to each weight and the chances of adding a connection or a node
+    pub neat_weight_rate: f32, // MAX 1.0
+    pub neat_weight_size: f32,
+    pub neat_add_connection: f32, // MAX 1.0
+    pub neat_add_node: f32,       // MAX 1.0
+    // chance that each entry of an FSM genotype's state table mutates on
+    // reproduction (also the chance of adding or removing a state)
+    pub fsm_mutation_rate: f32, // MAX 1.0
+    pub fsm_max_states: u16,
+    // chance that each instruction of a giles genotype mutates on reproduction
+    pub giles_mutation_rate: f32, // MAX 1.0
+
+    // the energy of all grass in config files from before plant species,
+    // clamp_plants moves it to the first plant
+    #[serde(skip_serializing)]
+    pub grass_energy: Option<i32>,
+
+    // the species of plant that grow in the world, eating a plant gains
+    // the energy it currently holds (see eyes2-lib/src/world/vegetation.rs)
+    pub plants: Vec<Plant>,
 }
 
 impl Settings {
     pub fn load() -> Settings {
         let mut settings: Settings = confy::load("eyes2", None).unwrap();
-        settings.size = settings.size.clamp(10, 200);
-        settings.grass_count = settings.grass_count.clamp(0, settings.size.pow(2));
-        for creature in settings.creatures.iter_mut() {
-            creature.1 = creature.1.clamp(0, settings.size.pow(2));
+        settings.clamp();
+        settings
+    }
+
+    // load a settings file in the same TOML format as the config, unlike
+    // confy a missing file is an error rather than written with defaults
+    pub fn load_path(path: &str) -> Result<Settings, Box<dyn Error>> {
+        if !std::path::Path::new(path).exists() {
+            return Err(format!("no settings file at {}", path).into());
         }
-        settings.creature_move_rate = settings.creature_move_rate.clamp(0.0, 1.0);
+        let mut settings: Settings = confy::load_path(path)?;
+        settings.clamp();
+        Ok(settings)
+    }
 
-        settings.speed = settings.speed.clamp(1, 10);
-        settings.grass_rate = settings.grass_rate.clamp(1, 100);
+    // force every field into its valid range
+    fn clamp(&mut self) {
+        self.size = self.size.clamp(10, 200);
+        self.grass_count = self.grass_count.clamp(0, self.size.pow(2));
+        for creature in self.creatures.iter_mut() {
+            creature.1 = creature.1.clamp(0, self.size.pow(2));
+        }
+        // new creatures take a random energy from the range, which must
+        // not be empty
+        let (a, b) = self.creature_initial_energy;
+        let (low, high) = (a.min(b), a.max(b));
+        self.creature_initial_energy = (low, high.max(low + 1));
+        self.creature_reproduction_energy = self.creature_reproduction_energy.max(1);
+        self.creature_move_rate = self.creature_move_rate.clamp(0.0, 1.0);
+        self.corpse_energy = self.corpse_energy.max(0);
+        self.corpse_decay = self.corpse_decay.max(1);
+        self.scent_diffusion = self.scent_diffusion.clamp(0.0, 1.0);
+        self.scent_evaporation = self.scent_evaporation.clamp(0.0, 1.0);
+        self.scent_interval = self.scent_interval.max(1);
+        self.scent_max = self.scent_max.max(0.0);
+        self.neural_mutation_rate = self.neural_mutation_rate.clamp(0.0, 1.0);
+        self.neat_weight_rate = self.neat_weight_rate.clamp(0.0, 1.0);
+        self.neat_add_connection = self.neat_add_connection.clamp(0.0, 1.0);
+        self.neat_add_node = self.neat_add_node.clamp(0.0, 1.0);
+        self.fsm_mutation_rate = self.fsm_mutation_rate.clamp(0.0, 1.0);
+        self.fsm_max_states = self.fsm_max_states.max(1);
+        self.giles_mutation_rate = self.giles_mutation_rate.clamp(0.0, 1.0);
+        self.stats_history = self.stats_history.max(1);
+        self.autosave_keep = self.autosave_keep.clamp(1, 100);
+        self.restart_keep = self.restart_keep.clamp(1, 1000);
+        self.grass_regrowth = self.grass_regrowth.clamp(0.0, 1.0);
+        self.season_amplitude = self.season_amplitude.clamp(0.0, 0.99);
+        self.season_length = self.season_length.max(1);
+        self.fertile_radius = self.fertile_radius.min(self.size);
+        self.clamp_plants();
 
-        settings
+        self.speed = self.speed.clamp(1, 10);
+        self.grass_rate = self.grass_rate.clamp(1, 100);
+    }
+
+    // Make the plants usable by a world. Worlds call this themselves as
+    // saved worlds and settings files given on the command line are never
+    // clamped by load.
+    pub(crate) fn clamp_plants(&mut self) {
+        if self.plants.is_empty() {
+            self.plants.push(Plant::default());
+        }
+        self.plants.truncate(u8::MAX as usize);
+        if let Some(energy) = self.grass_energy.take() {
+            self.plants[0].energy = energy;
+        }
+        for plant in self.plants.iter_mut() {
+            plant.growth = plant.growth.clamp(0.0, 1.0);
+            plant.visibility = plant.visibility.clamp(0.0, 1.0);
+        }
     }
 
     pub fn save(&self, settings: Settings) {
@@ -58,6 +225,132 @@ impl Settings {
         confy::store("eyes2", None, Settings::default()).unwrap();
         Settings::default()
     }
+
+    // the full path of the config file that load and save use
+    pub fn path() -> String {
+        confy::get_configuration_file_path("eyes2", None)
+            .map_or_else(|e| e.to_string(), |p| p.display().to_string())
+    }
+
+    // every field in declaration order with its value as a line of YAML
+    pub fn fields(&self) -> Vec<SettingsField> {
+        self.to_mapping()
+            .iter()
+            .map(|(name, value)| {
+                let name = name.as_str().unwrap_or_default().to_string();
+                SettingsField {
+                    live: !RESET_FIELDS.contains(&name.as_str()),
+                    name,
+                    value: inline_yaml(value),
+                }
+            })
+            .collect()
+    }
+
+    // Set a field from a line of YAML as shown by fields(). The value must
+    // have the right type and be in range, otherwise nothing changes.
+    pub fn set_field(&mut self, name: &str, value: &str) -> Result<(), SettingsError> {
+        let mut mapping = self.to_mapping();
+        if !mapping.contains_key(name) {
+            return Err(SettingsError::UnknownField(name.to_string()));
+        }
+        let bad_value =
+            |e: serde_yaml::Error| SettingsError::BadValue(name.to_string(), e.to_string());
+        mapping.insert(name.into(), serde_yaml::from_str(value).map_err(bad_value)?);
+        let settings: Settings =
+            serde_yaml::from_value(Value::Mapping(mapping)).map_err(bad_value)?;
+
+        // anything load would clamp is out of range
+        let mut clamped = settings.clone();
+        clamped.clamp();
+        let (wanted, valid) = (settings.to_mapping(), clamped.to_mapping());
+        for (field, value) in valid.iter() {
+            if wanted.get(field) != Some(value) {
+                let field = field.as_str().unwrap_or_default().to_string();
+                return Err(SettingsError::OutOfRange(field, inline_yaml(value)));
+            }
+        }
+        *self = settings;
+        Ok(())
+    }
+
+    // these settings with the live fields taken from other
+    pub fn with_live_fields(&self, other: &Settings) -> Settings {
+        let mut mapping = self.to_mapping();
+        for (name, value) in other.to_mapping() {
+            if !RESET_FIELDS.contains(&name.as_str().unwrap_or_default()) {
+                mapping.insert(name, value);
+            }
+        }
+        serde_yaml::from_value(Value::Mapping(mapping)).unwrap()
+    }
+
+    fn to_mapping(&self) -> Mapping {
+        match serde_yaml::to_value(self).unwrap() {
+            Value::Mapping(mapping) => mapping,
+            _ => unreachable!("settings always serialize as a mapping"),
+        }
+    }
+}
+
+// a field of the settings for the settings editor
+#[derive(Clone, Debug)]
+pub struct SettingsField {
+    pub name: String,
+    pub value: String,
+    // true if a change takes effect in a running world
+    pub live: bool,
+}
+
+#[derive(Debug)]
+pub enum SettingsError {
+    UnknownField(String),
+    // the field and the parse error
+    BadValue(String, String),
+    // the field and the nearest valid value
+    OutOfRange(String, String),
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SettingsError::UnknownField(name) => write!(f, "unknown setting {}", name),
+            SettingsError::BadValue(name, error) => write!(f, "bad value for {}: {}", name, error),
+            SettingsError::OutOfRange(name, valid) => {
+                write!(f, "{} out of range, nearest valid value is {}", name, valid)
+            }
+        }
+    }
+}
+
+impl Error for SettingsError {}
+
+// a YAML value on one line using flow style for sequences and mappings
+fn inline_yaml(value: &Value) -> String {
+    match value {
+        Value::Sequence(items) => {
+            let items: Vec<String> = items.iter().map(inline_yaml).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Value::Mapping(mapping) => {
+            let items: Vec<String> = mapping
+                .iter()
+                .map(|(k, v)| format!("{}: {}", inline_yaml(k), inline_yaml(v)))
+                .collect();
+            format!("{{{}}}", items.join(", "))
+        }
+        // the settings floats are all f32 so show them without f64 noise
+        Value::Number(n) if n.is_f64() => (n.as_f64().unwrap() as f32).to_string(),
+        // quote strings that would not read back as themselves e.g. "*"
+        Value::String(s) => match serde_yaml::from_str::<Value>(s) {
+            Ok(Value::String(read)) if read == *s => s.clone(),
+            _ => format!("{:?}", s),
+        },
+        other => serde_yaml::to_string(other)
+            .unwrap_or_default()
+            .trim_end()
+            .to_string(),
+    }
 }
 
 impl ::std::default::Default for Settings {
@@ -65,12 +358,42 @@ impl ::std::default::Default for Settings {
         Settings {
             size: 40,
             grass_count: 1000,
-            grass_energy: 1000,
             creature_initial_energy: (10000, 20000),
             creature_reproduction_energy: 100000,
             creature_move_energy: 10,
             creature_idle_energy: 1,
             creature_move_rate: 0.005,
+            corpse_energy: 0,
+            corpse_decay: 1,
+            scent_energy: 1,
+            scent_max: 100.0,
+            scent_diffusion: 0.2,
+            scent_evaporation: 0.05,
+            scent_interval: 100,
+            signal_energy: 10,
+            signal_radius: 5,
+            mating: true,
+            mate_energy: 5000,
+            species_interval: 1000,
+            species_threshold: 0.3,
+            stats_interval: 1000,
+            stats_sample: 20,
+            stats_history: 10000,
+            heatmap_interval: 100,
+            autosave_ticks: 0,
+            autosave_minutes: 0,
+            autosave_keep: 3,
+            restart_policy: RestartPolicy::Fresh,
+            restart_keep: 10,
+            restart_threshold: 20,
+
+            grass_model: GrassModel::Spread,
+            grass_spawn: 10,
+            grass_regrowth: 0.5,
+            fertile_zones: 4,
+            fertile_radius: 5,
+            season_amplitude: 0.0,
+            season_length: 10_000_000,
 
             speed: 9,
             grass_rate: 85,
@@ -80,7 +403,36 @@ impl ::std::default::Default for Settings {
                 ("noop".to_string(), 10),
                 ("giles".to_string(), 10),
                 ("looker".to_string(), 10),
+                ("neural".to_string(), 10),
+                ("neat".to_string(), 10),
+                ("fsm".to_string(), 10),
+            ],
+
+            neural_mutation_rate: 0.05,
+            neural_mutation_size: 0.5,
+            neat_weight_rate: 0.05,
+            neat_weight_size: 0.5,
+            neat_add_connection: 0.1,
+            neat_add_node: 0.03,
+            fsm_mutation_rate: 0.05,
+            fsm_max_states: 16,
+            giles_mutation_rate: 0.02,
+
+            grass_energy: None,
+            plants: vec![
+                Plant::default(),
+                Plant {
+                    name: "berry".to_string(),
+                    sigil: '*',
+                    energy: 3000,
+                    growth: 0.2,
+                    visibility: 0.5,
+                },
             ],
         }
     }
 }
+
+#[path = "settings_test.rs"]
+#[cfg(test)]
+mod test;
@@ -0,0 +1,114 @@
+use super::*;
+
+#[test]
+fn test_fields_read_back() {
+    let settings = Settings::default();
+    let fields = settings.fields();
+    assert_eq!(fields[0].name, "size");
+    assert!(!fields[0].live);
+    assert_eq!(fields.last().unwrap().name, "plants");
+
+    // every value as shown can be set again without change
+    let mut copy = settings.clone();
+    for field in fields.iter() {
+        copy.set_field(&field.name, &field.value).unwrap();
+    }
+    assert_eq!(copy.to_mapping(), settings.to_mapping());
+}
+
+#[test]
+fn test_set_field() {
+    let mut settings = Settings::default();
+    settings.set_field("creature_move_energy", "25").unwrap();
+    assert_eq!(settings.creature_move_energy, 25);
+    settings
+        .set_field("creatures", "[[giles, 5], [fsm, 7]]")
+        .unwrap();
+    assert_eq!(settings.creatures[1], ("fsm".to_string(), 7));
+
+    assert!(matches!(
+        settings.set_field("no_such_field", "1"),
+        Err(SettingsError::UnknownField(_))
+    ));
+    assert!(matches!(
+        settings.set_field("mating", "sometimes"),
+        Err(SettingsError::BadValue(..))
+    ));
+    // a smaller world cannot hold the grass so nothing changes
+    match settings.set_field("size", "10") {
+        Err(SettingsError::OutOfRange(field, valid)) => {
+            assert_eq!((field.as_str(), valid.as_str()), ("grass_count", "100"))
+        }
+        other => panic!("expected out of range, got {:?}", other),
+    }
+    assert_eq!(settings.size, 40);
+}
+
+#[test]
+fn test_with_live_fields() {
+    let settings = Settings::default();
+    let mut changed = settings.clone();
+    changed.set_field("size", "50").unwrap();
+    changed.set_field("creature_move_rate", "0.5").unwrap();
+
+    let live = settings.with_live_fields(&changed);
+    assert_eq!(live.size, 40);
+    assert_eq!(live.creature_move_rate, 0.5);
+}
+
+#[test]
+fn test_clamp_plants() {
+    // a config file from before plant species
+    let mut settings: Settings = serde_yaml::from_str("grass_energy: 500").unwrap();
+    settings.clamp_plants();
+    assert_eq!(settings.plants[0].energy, 500);
+    assert_eq!(settings.plants[1].name, "berry");
+
+    let mut settings: Settings = serde_yaml::from_str("plants: []").unwrap();
+    settings.clamp_plants();
+    assert_eq!(settings.plants.len(), 1);
+}
+
+#[test]
+fn test_load_path() {
+    let path = std::env::temp_dir().join(format!("eyes2-base-{}.toml", std::process::id()));
+    let path = path.to_str().unwrap();
+    assert!(Settings::load_path(path).is_err());
+
+    let settings = Settings {
+        size: 5000,
+        ..Settings::default()
+    };
+    confy::store_path(path, settings).unwrap();
+    let loaded = Settings::load_path(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(loaded.size, 200);
+}
+
+#[test]
+fn test_creature_energy_ranges() {
+    let mut settings = Settings::default();
+    for bad in ["[5, 5]", "[9, 1]"] {
+        assert!(matches!(
+            settings.set_field("creature_initial_energy", bad),
+            Err(SettingsError::OutOfRange(..))
+        ));
+    }
+    assert!(matches!(
+        settings.set_field("creature_reproduction_energy", "0"),
+        Err(SettingsError::OutOfRange(..))
+    ));
+    assert_eq!(settings.creature_initial_energy, (10000, 20000));
+
+    let mut settings = Settings {
+        creature_initial_energy: (9, 1),
+        creature_reproduction_energy: 0,
+        ..Settings::default()
+    };
+    settings.clamp();
+    assert_eq!(settings.creature_initial_energy, (1, 9));
+    assert_eq!(settings.creature_reproduction_energy, 1);
+    settings.creature_initial_energy = (5, 5);
+    settings.clamp();
+    assert_eq!(settings.creature_initial_energy, (5, 6));
+}
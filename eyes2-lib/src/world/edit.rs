@@ -0,0 +1,57 @@
+//! Edit the world by painting cells with a brush, used by the GUI's edit
+//! mode. Creatures are never painted over.
+
+use super::*;
+
+// what painting a cell puts in it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Brush {
+    Wall,
+    Grass,
+    // clear walls, grass and corpses
+    Erase,
+}
+
+impl Brush {
+    pub const ALL: [Brush; 3] = [Brush::Wall, Brush::Grass, Brush::Erase];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Brush::Wall => "wall",
+            Brush::Grass => "grass",
+            Brush::Erase => "erase",
+        }
+    }
+}
+
+impl World {
+    pub fn paint(&mut self, coord: Coord, brush: Brush) {
+        let size = self.config.size as i32;
+        if coord.x < 0 || coord.y < 0 || coord.x >= size || coord.y >= size {
+            return;
+        }
+
+        let cell = self.grid.get_cell(coord);
+        match (brush, cell) {
+            (_, Cell::Entity(..)) | (Brush::Grass, Cell::Grass(..)) => return,
+            (Brush::Grass, _) => {
+                let species = self.random_species();
+                let energy = self.config.plants[species as usize].energy;
+                self.grid.set_cell(coord, Cell::Empty);
+                self.grid.add_grass(coord, species, energy);
+            }
+            (Brush::Wall, _) => {
+                self.grid.remove_grass(coord);
+                self.grid.set_cell(coord, Cell::Wall);
+            }
+            (Brush::Erase, _) => {
+                self.grid.remove_grass(coord);
+                self.grid.set_cell(coord, Cell::Empty);
+            }
+        }
+        // corpses that are painted over drop out of the decay list
+        if let Cell::Corpse(_) = cell {
+            self.corpses.retain(|corpse| *corpse != coord);
+        }
+    }
+}
@@ -1,7 +1,10 @@
+use super::heatmap::Heatmaps;
+use super::world::stats::StatsSample;
 use chrono::{DateTime, Utc};
 
 use direction;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 // the representation of the world cells plus some metadata
 // used to pass information to the renderer thread
@@ -10,6 +13,21 @@ pub struct WorldGrid {
     // the grid of cells
     #[serde(skip)]
     grid: Vec<Cell>,
+    // the strength of the scent trail in each cell of the grid
+    #[serde(skip)]
+    scent: Vec<f32>,
+    // the indices of the cells that look different since the last snapshot
+    // (with a flag per cell to list each once) and true if the next
+    // snapshot must hold every cell
+    #[serde(skip)]
+    dirty: Vec<usize>,
+    #[serde(skip)]
+    is_dirty: Vec<bool>,
+    #[serde(skip)]
+    needs_full: bool,
+    // the species_epoch of the last snapshot that held species_of
+    #[serde(skip)]
+    sent_species_epoch: Option<u64>,
     // the dimensions of the (square) grid
     size: u16,
     // number of grass blocks in the world
@@ -28,6 +46,61 @@ pub struct WorldGrid {
     pub start_time: DateTime<Utc>,
     // next unique id to assign to an Entity
     pub next_id: u64,
+    // centres of the fertile zones used by the fertile grass model
+    #[serde(default)]
+    pub fertile_zones: Vec<direction::Coord>,
+    // current seasonal multiplier of the grass growth rate
+    #[serde(default)]
+    pub season: f32,
+    // the sigil used to render each species of plant
+    #[serde(skip)]
+    pub plant_sigils: Vec<char>,
+    // the species id of each creature (by creature id) from the last
+    // clustering, newer creatures have no species yet
+    #[serde(skip)]
+    pub species_of: HashMap<u64, u32>,
+    // the position and energy of each creature (by creature id), only
+    // filled in for the GUI by World::snapshot
+    #[serde(skip)]
+    pub energies: HashMap<u64, (direction::Coord, i32)>,
+    // the size of each species, largest first
+    #[serde(skip)]
+    pub species: Vec<SpeciesSize>,
+    // counts the clusterings, the creatures may have changed colour
+    #[serde(skip)]
+    pub species_epoch: u64,
+    // heatmaps of activity in the world for the GUI overlays
+    #[serde(skip)]
+    pub heatmaps: Heatmaps,
+    // the most recent sample of the population statistics
+    #[serde(skip)]
+    pub latest_stats: Option<StatsSample>,
+}
+
+// the number of living members of a species
+#[derive(Clone, Debug)]
+pub struct SpeciesSize {
+    pub id: u32,
+    pub sigil: char,
+    pub size: usize,
+}
+
+// the optional parts of a snapshot, the GUI asks for the parts it draws
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SnapshotParts {
+    // the heatmaps, while an overlay is shown
+    pub heatmaps: bool,
+    // the creatures' energies, while they are drawn
+    pub energies: bool,
+}
+
+// the cells sent to the GUI with a snapshot of the grid
+#[derive(Clone, Debug)]
+pub enum CellUpdate {
+    // every cell, for a new or loaded world
+    Full(Vec<Cell>),
+    // the index and new value of each cell that changed
+    Changed(Vec<(usize, Cell)>),
 }
 
 // represent the contents of a single cell in the world
@@ -37,19 +110,41 @@ pub enum Cell {
     Empty,
     // the cell is occupied by a Creature (with a unique number)
     Entity(u64, char),
-    // the cell is occupied by a block of grass
-    Grass,
+    // the cell is occupied by a plant (species index into Settings::plants
+    // and the energy it currently holds)
+    Grass(u8, i32),
+    // the cell holds the decaying corpse of a creature (with its energy)
+    Corpse(i32),
     // the cell us occupied by an impenetrable wall
     Wall,
 }
 
+impl Cell {
+    // true if the GUI draws the two cells the same way
+    fn looks_like(&self, other: &Cell) -> bool {
+        match (self, other) {
+            (Cell::Empty, Cell::Empty) | (Cell::Wall, Cell::Wall) => true,
+            (Cell::Corpse(_), Cell::Corpse(_)) => true,
+            (Cell::Grass(a, _), Cell::Grass(b, _)) => a == b,
+            (Cell::Entity(a, _), Cell::Entity(b, _)) => a == b,
+            _ => false,
+        }
+    }
+}
+
 impl WorldGrid {
     pub fn new(size: u16, grass_rate: u64, speed: u64, restarts: u64) -> WorldGrid {
         // create a square 2d vector of empty cells
         let grid = vec![Cell::Empty; size.pow(2) as usize];
+        let scent = vec![0.0; size.pow(2) as usize];
 
         WorldGrid {
             grid,
+            scent,
+            dirty: Vec::new(),
+            is_dirty: vec![false; size.pow(2) as usize],
+            needs_full: true,
+            sent_species_epoch: None,
             size,
             grass_count: 0,
             grass_rate,
@@ -59,12 +154,28 @@ impl WorldGrid {
             restarts,
             start_time: Utc::now(),
             next_id: 0,
+            fertile_zones: Vec::new(),
+            season: 1.0,
+            plant_sigils: Vec::new(),
+            species_of: HashMap::new(),
+            energies: HashMap::new(),
+            species: Vec::new(),
+            species_epoch: 0,
+            heatmaps: Heatmaps::new(size),
+            latest_stats: None,
         }
     }
 
     // restore correct size of the grid after loading from a file
     pub fn expand(&mut self, size: u16) {
         self.grid = vec![Cell::Empty; size.pow(2) as usize];
+        self.scent = vec![0.0; size.pow(2) as usize];
+        self.dirty.clear();
+        self.is_dirty = vec![false; size.pow(2) as usize];
+        self.needs_full = true;
+        self.heatmaps = Heatmaps::new(size);
+        // the grass is counted again as it is added back into the empty grid
+        self.grass_count = 0;
     }
 
     pub fn get_size(&self) -> u16 {
@@ -108,23 +219,109 @@ impl WorldGrid {
     }
 
     pub fn set_cell(&mut self, position: direction::Coord, value: Cell) {
-        self.grid[(position.x + position.y * self.size as i32) as usize] = value;
+        let index = (position.x + position.y * self.size as i32) as usize;
+        if !self.is_dirty[index] && !self.grid[index].looks_like(&value) {
+            self.is_dirty[index] = true;
+            self.dirty.push(index);
+        }
+        self.grid[index] = value;
     }
 
-    pub fn add_grass(&mut self, coord: direction::Coord) {
+    // A copy of the grid for the GUI without its cells or scent, along with
+    // the cells that changed since the last snapshot. The heatmaps are only
+    // copied when asked for and the species only when they have changed,
+    // so the size of a snapshot does not grow with the size of the world.
+    pub fn snapshot(&mut self, parts: SnapshotParts) -> (WorldGrid, CellUpdate) {
+        // take the big vectors out so that they are not cloned
+        let grid = std::mem::take(&mut self.grid);
+        let scent = std::mem::take(&mut self.scent);
+        let dirty = std::mem::take(&mut self.dirty);
+        let is_dirty = std::mem::take(&mut self.is_dirty);
+        let heatmaps = std::mem::take(&mut self.heatmaps);
+        let species_of = std::mem::take(&mut self.species_of);
+        let mut copy = self.clone();
+        (self.grid, self.scent, self.is_dirty) = (grid, scent, is_dirty);
+        (self.heatmaps, self.species_of) = (heatmaps, species_of);
+
+        if parts.heatmaps {
+            copy.heatmaps = self.heatmaps.clone();
+        }
+        if self.needs_full || self.sent_species_epoch != Some(self.species_epoch) {
+            copy.species_of = self.species_of.clone();
+            self.sent_species_epoch = Some(self.species_epoch);
+        }
+
+        let update = match self.needs_full {
+            true => CellUpdate::Full(self.grid.clone()),
+            false => CellUpdate::Changed(dirty.iter().map(|i| (*i, self.grid[*i])).collect()),
+        };
+        for index in dirty.iter() {
+            self.is_dirty[*index] = false;
+        }
+        self.needs_full = false;
+        (copy, update)
+    }
+
+    // fill in the cells of a snapshot from the previous grid and an update,
+    // the species are kept from the previous grid if they have not changed
+    pub fn apply_update(&mut self, previous: Option<WorldGrid>, update: CellUpdate) {
+        let cells = self.size.pow(2) as usize;
+        self.grid = match update {
+            CellUpdate::Full(grid) => grid,
+            CellUpdate::Changed(changes) => {
+                let mut grid = Vec::new();
+                if let Some(previous) = previous {
+                    if previous.species_epoch == self.species_epoch {
+                        self.species_of = previous.species_of;
+                    }
+                    grid = previous.grid;
+                }
+                grid.resize(cells, Cell::Empty);
+                for (index, cell) in changes {
+                    grid[index] = cell;
+                }
+                grid
+            }
+        };
+    }
+
+    pub fn get_scent(&self, position: direction::Coord) -> f32 {
+        if self.check_coord(position) {
+            self.scent[(position.x + position.y * self.size as i32) as usize]
+        } else {
+            0.0
+        }
+    }
+
+    pub fn add_scent(&mut self, position: direction::Coord, amount: f32) {
+        if self.check_coord(position) {
+            self.scent[(position.x + position.y * self.size as i32) as usize] += amount;
+        }
+    }
+
+    // replace the whole scent layer, used when it diffuses
+    pub fn set_scent_layer(&mut self, scent: Vec<f32>) {
+        self.scent = scent;
+    }
+
+    pub fn add_grass(&mut self, coord: direction::Coord, species: u8, energy: i32) {
         if let Cell::Empty = self.get_cell(coord) {
-            self.set_cell(coord, Cell::Grass);
+            self.set_cell(coord, Cell::Grass(species, energy));
             self.grass_count += 1;
         }
     }
 
     pub fn remove_grass(&mut self, coord: direction::Coord) {
-        if let Cell::Grass = self.get_cell(coord) {
+        if let Cell::Grass(_, _) = self.get_cell(coord) {
             self.set_cell(coord, Cell::Empty);
             self.grass_count -= 1;
         }
     }
 
+    pub fn plant_sigil(&self, species: u8) -> char {
+        *self.plant_sigils.get(species as usize).unwrap_or(&'o')
+    }
+
     fn check_coord(&self, coord: direction::Coord) -> bool {
         coord.x >= 0 && coord.x < self.size as i32 && coord.y >= 0 && coord.y < self.size as i32
     }
@@ -0,0 +1,95 @@
+//! Heatmaps of where things happen in the world, drawn as overlays by the GUI
+//!
+//! Visits, deaths and births are counted as they happen. Grass density and
+//! creature energy are sampled every heatmap_interval ticks: the grass map
+//! counts the samples in which each cell held grass and the energy map holds
+//! the mean energy of the creatures in the region around each cell at the
+//! last sample. The scent map is the scent layer as of its last spread plus
+//! any scent left since.
+
+use direction::Coord;
+
+// creature energy is averaged over square regions of this size
+pub const REGION: i32 = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Heatmap {
+    Visits,
+    Deaths,
+    Births,
+    Grass,
+    Energy,
+    Scent,
+}
+
+impl Heatmap {
+    pub const ALL: [Heatmap; 6] = [
+        Heatmap::Visits,
+        Heatmap::Deaths,
+        Heatmap::Births,
+        Heatmap::Grass,
+        Heatmap::Energy,
+        Heatmap::Scent,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Heatmap::Visits => "visits",
+            Heatmap::Deaths => "deaths",
+            Heatmap::Births => "births",
+            Heatmap::Grass => "grass",
+            Heatmap::Energy => "energy",
+            Heatmap::Scent => "scent",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Heatmaps {
+    size: u16,
+    // one layer of cells per Heatmap
+    layers: [Vec<f32>; 6],
+}
+
+impl Heatmaps {
+    pub fn new(size: u16) -> Heatmaps {
+        let cells = size.pow(2) as usize;
+        Heatmaps {
+            size,
+            layers: [(); 6].map(|_| vec![0.0; cells]),
+        }
+    }
+
+    pub fn add(&mut self, map: Heatmap, coord: Coord, amount: f32) {
+        if let Some(index) = self.index(coord) {
+            self.layers[map as usize][index] += amount;
+        }
+    }
+
+    pub fn get(&self, map: Heatmap, coord: Coord) -> f32 {
+        match self.index(coord) {
+            Some(index) => self.layers[map as usize][index],
+            None => 0.0,
+        }
+    }
+
+    // the largest value in a heatmap, used to scale it for display
+    pub fn max(&self, map: Heatmap) -> f32 {
+        self.layers[map as usize]
+            .iter()
+            .cloned()
+            .fold(0.0, f32::max)
+    }
+
+    pub fn set_layer(&mut self, map: Heatmap, layer: Vec<f32>) {
+        self.layers[map as usize] = layer;
+    }
+
+    fn index(&self, coord: Coord) -> Option<usize> {
+        let size = self.size as i32;
+        match coord.x >= 0 && coord.x < size && coord.y >= 0 && coord.y < size {
+            true => Some((coord.x + coord.y * size) as usize),
+            false => None,
+        }
+    }
+}
@@ -1,7 +1,15 @@
 // re-export API structures to the world module root
-pub use self::grid::{Cell, WorldGrid};
-pub use self::world::store::save_world;
+pub use self::grid::{Cell, CellUpdate, SnapshotParts, SpeciesSize, WorldGrid};
+pub use self::heatmap::{Heatmap, Heatmaps};
+pub use self::world::edit::Brush;
+pub use self::world::restart::{RestartPolicy, Seed};
+pub use self::world::stats::{write_stats_csv, GenotypeStats, StatsSample, StatsSeries};
+pub use self::world::store::{load_checkpoint, load_world_file, save_stats, save_world, Autosave};
+pub use self::world::until::Until;
+pub use self::world::vegetation::{GrassModel, Plant};
 pub use self::world::World;
 
 pub mod grid;
+pub mod heatmap;
+#[allow(clippy::module_inception)]
 pub mod world;
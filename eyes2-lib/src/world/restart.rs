@@ -0,0 +1,120 @@
+//! Carry the best genomes over when the creatures die out.
+//!
+//! As creatures die the world keeps the genomes of the restart_keep best of
+//! them, best being the most children and then the longest life. The
+//! restart policy decides what is done with them:
+//!
+//! - fresh: they are not used, the next world starts with new creatures
+//! - reseed: the next world starts with copies of them in place of new
+//!   creatures, as many as the settings ask for
+//! - inject: whenever the population falls below restart_threshold copies
+//!   of them are added to make it up, the world only restarts (reseeded)
+//!   if the creatures die out anyway
+
+use super::*;
+use crate::entity::Genotype;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RestartPolicy {
+    Fresh,
+    Reseed,
+    Inject,
+}
+
+// the genome of a creature that did well
+#[derive(Clone)]
+pub struct Seed {
+    pub children: u32,
+    pub lifetime: u64,
+    pub genotype: Box<dyn Genotype>,
+}
+
+impl World {
+    // the best genomes seen so far, best first
+    pub fn seeds(&self) -> &[Seed] {
+        &self.seeds
+    }
+
+    // Populate a new world with copies of the seeds in place of new
+    // creatures, with no seeds this is the same as populate. The seeds are
+    // kept for the next restart.
+    pub fn populate_from(&mut self, seeds: Vec<Seed>) {
+        if seeds.is_empty() {
+            self.populate();
+            return;
+        }
+        self.seeds = seeds;
+        let count: u64 = self.config.creatures.iter().map(|(_, n)| *n as u64).sum();
+        self.plant_grass();
+        self.inject(count);
+        self.apply_updates();
+        // the first creatures are not births
+        self.grid.heatmaps = Heatmaps::new(self.config.size);
+    }
+
+    // keep the genome of a creature that has died if it is one of the best
+    pub(super) fn record_seed(&mut self, creature: &Creature) {
+        if self.config.restart_policy == RestartPolicy::Fresh {
+            return;
+        }
+        let seed = (
+            creature.children(),
+            self.grid.ticks.saturating_sub(creature.born()),
+        );
+        let keep = self.config.restart_keep;
+        let worst = self.seeds.last().map(|s| (s.children, s.lifetime));
+        if self.seeds.len() >= keep && worst.is_some_and(|worst| seed <= worst) {
+            return;
+        }
+        let at = self
+            .seeds
+            .partition_point(|s| (s.children, s.lifetime) >= seed);
+        self.seeds.insert(
+            at,
+            Seed {
+                children: seed.0,
+                lifetime: seed.1,
+                genotype: dyn_clone::clone_box(creature.genotype()),
+            },
+        );
+        self.seeds.truncate(keep);
+    }
+
+    // make the population up to restart_threshold (inject policy)
+    pub(super) fn inject_below_threshold(&mut self) {
+        let threshold = self.config.restart_threshold;
+        if self.config.restart_policy == RestartPolicy::Inject && self.creature_count() < threshold
+        {
+            self.inject(threshold - self.creature_count());
+        }
+    }
+
+    // add count creatures at random, copies of the seeds in turn or new
+    // creatures of the genotypes in the settings if there are no seeds yet
+    fn inject(&mut self, count: u64) {
+        let names: Vec<String> = self.config.creatures.iter().map(|c| c.0.clone()).collect();
+        for i in 0..count as usize {
+            let genotype = match (self.seeds.len(), names.len()) {
+                (0, 0) => return,
+                (0, n) => match new_genotype(&names[i % n], self.config.clone()) {
+                    Ok(genotype) => genotype,
+                    Err(_) => continue,
+                },
+                (n, _) => {
+                    let mut genotype = self.seeds[i % n].genotype.clone();
+                    genotype.set_config(self.config.clone());
+                    genotype
+                }
+            };
+            let size = self.config.size as i32;
+            let coord = Coord {
+                x: self.rng.i32(0..size),
+                y: self.rng.i32(0..size),
+            };
+            let creature = Creature::new(genotype, coord, self.config.clone(), self.tx.clone());
+            self.tx.send(Update::AddEntity(Box::new(creature))).unwrap();
+        }
+    }
+}
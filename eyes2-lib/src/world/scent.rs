@@ -0,0 +1,59 @@
+//! The scent layer. Creatures may leave scent in the cell they occupy,
+//! at every scent tick a fraction of each cell's scent spreads to its
+//! neighbours and a fraction evaporates.
+//!
+//! The diffusion and evaporation rates are chosen in Settings.
+
+use super::*;
+use direction::Directions;
+
+// scent weaker than this has evaporated completely
+const SCENT_MIN: f32 = 0.01;
+
+impl World {
+    pub(super) fn spread_scent(&mut self) {
+        let size = self.config.size as i32;
+        let keep = 1.0 - self.config.scent_diffusion;
+        let share = self.config.scent_diffusion / 8.0;
+        let remain = 1.0 - self.config.scent_evaporation;
+        let index = |coord: Coord| (coord.x + coord.y * size) as usize;
+        let mut scent = vec![0.0; (size * size) as usize];
+
+        for x in 0..size {
+            for y in 0..size {
+                let coord = Coord::new(x, y);
+                let strength = self.grid.get_scent(coord);
+                if strength == 0.0 {
+                    continue;
+                }
+                scent[index(coord)] += strength * keep;
+                // scent that spreads off the edge of the world is lost
+                for direction in Directions {
+                    let next = coord + direction.coord();
+                    if next.x >= 0 && next.x < size && next.y >= 0 && next.y < size {
+                        scent[index(next)] += strength * share;
+                    }
+                }
+            }
+        }
+
+        self.has_scent = false;
+        for strength in scent.iter_mut() {
+            *strength *= remain;
+            if *strength < SCENT_MIN {
+                *strength = 0.0;
+            } else {
+                self.has_scent = true;
+            }
+        }
+        // the scent overlay shows the scent layer as it is now
+        self.grid.heatmaps.set_layer(Heatmap::Scent, scent.clone());
+        self.grid.set_scent_layer(scent);
+    }
+
+    pub(super) fn add_scent(&mut self, coord: Coord, amount: f32) {
+        self.grid.add_scent(coord, amount);
+        self.grid.heatmaps.add(Heatmap::Scent, coord, amount);
+        self.has_scent = true;
+    }
+}
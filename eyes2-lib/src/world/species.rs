@@ -0,0 +1,81 @@
+//! Group the creatures in the world into species by the distance between
+//! their genomes.
+//!
+//! Each creature joins the first species whose representative genome is
+//! within species_threshold of its own, otherwise it founds a new species.
+//! Species keep their id from one clustering to the next (which gives them
+//! a stable colour in the GUI) and the first member found becomes the
+//! representative for the next clustering so that a species can drift.
+
+use super::*;
+use crate::entity::Genotype;
+use crate::world::grid::SpeciesSize;
+
+// a group of creatures with similar genomes
+pub(super) struct Species {
+    id: u32,
+    // the genome that creatures are compared with
+    representative: Box<dyn Genotype>,
+    sigil: char,
+}
+
+impl World {
+    pub(super) fn cluster_species(&mut self) {
+        let threshold = self.config.species_threshold;
+        let mut ids: Vec<u64> = self.creatures.keys().cloned().collect();
+        ids.sort();
+
+        // the size and first member of each species
+        let mut sizes = vec![0; self.species.len()];
+        let mut firsts: Vec<Option<u64>> = vec![None; self.species.len()];
+        self.grid.species_of.clear();
+        for id in ids {
+            let creature = &self.creatures[&id];
+            let genotype = creature.genotype();
+            let found = self.species.iter().position(|species| {
+                genotype
+                    .distance(species.representative.as_ref())
+                    .is_some_and(|distance| distance <= threshold)
+            });
+            let index = found.unwrap_or_else(|| {
+                self.species.push(Species {
+                    id: self.next_species_id,
+                    representative: dyn_clone::clone_box(genotype),
+                    sigil: creature.get_sigil(),
+                });
+                self.next_species_id += 1;
+                sizes.push(0);
+                firsts.push(None);
+                self.species.len() - 1
+            });
+            sizes[index] += 1;
+            firsts[index].get_or_insert(id);
+            self.grid.species_of.insert(id, self.species[index].id);
+        }
+
+        // extinct species are forgotten
+        let mut survivors = Vec::new();
+        for (mut species, first) in self.species.drain(..).zip(firsts) {
+            if let Some(first) = first {
+                species.representative = dyn_clone::clone_box(self.creatures[&first].genotype());
+                survivors.push(species);
+            }
+        }
+        self.species = survivors;
+
+        sizes.retain(|size| *size > 0);
+        let mut summary: Vec<SpeciesSize> = self
+            .species
+            .iter()
+            .zip(sizes)
+            .map(|(species, size)| SpeciesSize {
+                id: species.id,
+                sigil: species.sigil,
+                size,
+            })
+            .collect();
+        summary.sort_by(|a, b| b.size.cmp(&a.size).then(a.id.cmp(&b.id)));
+        self.grid.species = summary;
+        self.grid.species_epoch += 1;
+    }
+}
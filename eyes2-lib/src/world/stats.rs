@@ -0,0 +1,182 @@
+//! Collect a time series of population statistics
+//!
+//! Every stats_interval ticks the world records the population along with
+//! some measures of the genetic diversity of each genotype:
+//!
+//! - distinct: the number of distinct genomes (by genome hash)
+//! - entropy: the Shannon entropy (in bits) of the genome hashes, 0 when
+//!   every creature has the same genome
+//! - mean distance: the mean genome distance between pairs of creatures
+//!   in a random sample of stats_sample creatures
+//!
+//! A converged population has few distinct genomes, low entropy and a small
+//! mean distance. The series can be written out as CSV.
+
+use super::*;
+use crate::entity::Genotype;
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{self, Write};
+
+// the diversity of the creatures of one genotype
+#[derive(Clone, Debug)]
+pub struct GenotypeStats {
+    pub name: String,
+    pub count: usize,
+    pub distinct: usize,
+    pub entropy: f32,
+    pub mean_distance: f32,
+}
+
+// the state of the population at one point in time
+#[derive(Clone, Debug)]
+pub struct StatsSample {
+    pub ticks: u64,
+    pub creatures: u64,
+    pub grass: usize,
+    pub species: usize,
+    pub mean_energy: f32,
+    // one entry per genotype, in name order
+    pub genotypes: Vec<GenotypeStats>,
+}
+
+pub type StatsSeries = VecDeque<StatsSample>;
+
+impl World {
+    pub fn stats(&self) -> &StatsSeries {
+        &self.stats
+    }
+
+    // the number of creatures of each genotype by name, including the
+    // genotypes in the settings that have died out
+    pub fn genotype_counts(&self) -> BTreeMap<String, usize> {
+        let mut counts: BTreeMap<String, usize> = self
+            .config
+            .creatures
+            .iter()
+            .map(|(name, _)| (name.clone(), 0))
+            .collect();
+        for creature in self.creatures.values() {
+            let name = creature.genotype().describe().name;
+            *counts.entry(name).or_default() += 1;
+        }
+        counts
+    }
+
+    pub(super) fn sample_stats(&mut self) {
+        // group the genotypes by name
+        let mut groups: HashMap<String, Vec<(&dyn Genotype, u64)>> = HashMap::new();
+        for creature in self.creatures.values() {
+            let genotype = creature.genotype();
+            let info = genotype.describe();
+            groups
+                .entry(info.name)
+                .or_default()
+                .push((genotype, info.genome_hash));
+        }
+
+        let mut genotypes: Vec<GenotypeStats> = groups
+            .into_iter()
+            .map(|(name, members)| diversity(name, &members, self.config.stats_sample, &self.rng))
+            .collect();
+        genotypes.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let energy: i64 = self.creatures.values().map(|c| c.energy() as i64).sum();
+        let sample = StatsSample {
+            ticks: self.grid.ticks,
+            creatures: self.creature_count(),
+            grass: self.grid.grass_count(),
+            species: self.grid.species.len(),
+            mean_energy: energy as f32 / self.creatures.len().max(1) as f32,
+            genotypes,
+        };
+        // the GUI charts are drawn from the samples it sees in the grid
+        self.grid.latest_stats = Some(sample.clone());
+        self.stats.push_back(sample);
+        while self.stats.len() > self.config.stats_history {
+            self.stats.pop_front();
+        }
+    }
+}
+
+fn diversity(
+    name: String,
+    members: &[(&dyn Genotype, u64)],
+    sample: usize,
+    rng: &FastRng,
+) -> GenotypeStats {
+    let mut counts: HashMap<u64, usize> = HashMap::new();
+    for (_, hash) in members {
+        *counts.entry(*hash).or_default() += 1;
+    }
+    let total = members.len() as f32;
+    let entropy = counts
+        .values()
+        .map(|count| {
+            let p = *count as f32 / total;
+            -p * p.log2()
+        })
+        .sum::<f32>();
+
+    // the distance between every pair in a random sample of the members
+    let mut sampled: Vec<&dyn Genotype> = members.iter().map(|(g, _)| *g).collect();
+    rng.shuffle(&mut sampled);
+    sampled.truncate(sample);
+    let mut distance = 0.0;
+    let mut pairs = 0;
+    for (i, a) in sampled.iter().enumerate() {
+        for b in sampled.iter().skip(i + 1) {
+            if let Some(d) = a.distance(*b) {
+                distance += d;
+                pairs += 1;
+            }
+        }
+    }
+
+    GenotypeStats {
+        name,
+        count: members.len(),
+        distinct: counts.len(),
+        // avoid reporting -0 for a single genome
+        entropy: entropy.max(0.0),
+        mean_distance: if pairs > 0 {
+            distance / pairs as f32
+        } else {
+            0.0
+        },
+    }
+}
+
+// write the series as CSV with one row per genotype per sample
+pub fn write_stats_csv(series: &StatsSeries, mut writer: impl Write) -> io::Result<()> {
+    writeln!(
+        writer,
+        "ticks,creatures,grass,species,mean_energy,genotype,count,distinct,entropy,mean_distance"
+    )?;
+    for sample in series.iter() {
+        // keep a row for samples after the population died out
+        if sample.genotypes.is_empty() {
+            writeln!(
+                writer,
+                "{},{},{},{},{:.1},,0,0,0,0",
+                sample.ticks, sample.creatures, sample.grass, sample.species, sample.mean_energy
+            )?;
+        }
+        for genotype in sample.genotypes.iter() {
+            writeln!(
+                writer,
+                "{},{},{},{},{:.1},{},{},{},{:.4},{:.4}",
+                sample.ticks,
+                sample.creatures,
+                sample.grass,
+                sample.species,
+                sample.mean_energy,
+                genotype.name,
+                genotype.count,
+                genotype.distinct,
+                genotype.entropy,
+                genotype.mean_distance
+            )?;
+        }
+    }
+    Ok(())
+}
@@ -1,5 +1,10 @@
 //! Save and load the world to/from a YAML file
+//!
+//! Checkpoints are saved to a rotating set of files named checkpoint-N.yaml.
+//! Each is written to a temporary file and renamed so that a crash while
+//! saving never spoils the checkpoints already saved.
 
+use super::edit::Brush;
 use super::*;
 use crate::entity::Creature;
 use crate::settings::Settings;
@@ -8,10 +13,13 @@ use serde::de;
 use serde::de::{Deserializer, MapAccess, Visitor};
 use serde::ser::{SerializeStruct, Serializer};
 use serde::{Deserialize, Serialize};
+use std::error::Error;
 use std::fmt;
-use std::fs::File;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
 
-const FIELDS: &'static [&'static str] = &["grid", "config", "creatures", "grasses"];
+const FIELDS: &[&str] = &["grid", "config", "creatures", "grasses", "corpses", "walls"];
 
 #[derive(Deserialize, Serialize)]
 struct CreatureSer {
@@ -19,15 +27,138 @@ struct CreatureSer {
     creature: Creature,
 }
 
+// older save files hold only the coordinate of each grass cell
+#[derive(Deserialize, Serialize)]
+struct GrassSer {
+    #[serde(flatten)]
+    coord: Coord,
+    #[serde(default)]
+    species: u8,
+    energy: Option<i32>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct CorpseSer {
+    coord: Coord,
+    energy: i32,
+}
+
 pub fn save_world(world: &World) {
     let file = File::create("world.yaml").unwrap();
     serde_yaml::to_writer(file, world).unwrap();
 }
 
+// the population time series is saved alongside the world
+pub fn save_stats(world: &World) -> Result<(), Box<dyn Error>> {
+    let file = File::create("stats.csv")?;
+    stats::write_stats_csv(world.stats(), file)?;
+    Ok(())
+}
+
+// saves checkpoints of a world when they are due, see
+// Settings::autosave_ticks
+pub struct Autosave {
+    next_tick: u64,
+    next_time: Option<Instant>,
+    // the checkpoint file to save next, None for the oldest on disk
+    slot: Option<usize>,
+}
+
+impl Autosave {
+    pub fn new(world: &World) -> Autosave {
+        let mut autosave = Autosave {
+            next_tick: 0,
+            next_time: None,
+            slot: None,
+        };
+        autosave.schedule(world);
+        autosave
+    }
+
+    // True when a checkpoint is due, the next one is then scheduled. The
+    // clock is read on every call so that a checkpoint is saved on time
+    // however fast or slow the world is ticking, reading it takes a few
+    // nanoseconds.
+    pub fn due(&mut self, world: &World) -> bool {
+        let ticks = world.grid.ticks;
+        let due = (world.config.autosave_ticks > 0 && ticks >= self.next_tick)
+            || self.next_time.is_some_and(|time| Instant::now() >= time);
+        if due {
+            self.schedule(world);
+        }
+        due
+    }
+
+    // Save a checkpoint of the world in dir, replacing the oldest when
+    // there are already autosave_keep of them. Returns its path.
+    pub fn save(&mut self, world: &World, dir: &Path) -> Result<PathBuf, Box<dyn Error>> {
+        let keep = world.config.autosave_keep.max(1);
+        let path = |slot: usize| dir.join(format!("checkpoint-{}.yaml", slot));
+        // a missing file is older than any
+        let slot = self.slot.filter(|slot| *slot < keep).unwrap_or_else(|| {
+            (0..keep)
+                .min_by_key(|slot| modified(&path(*slot)).unwrap_or(SystemTime::UNIX_EPOCH))
+                .unwrap_or(0)
+        });
+        self.slot = Some((slot + 1) % keep);
+
+        let temp = dir.join("checkpoint.yaml.tmp");
+        serde_yaml::to_writer(File::create(&temp)?, world)?;
+        fs::rename(&temp, path(slot))?;
+        Ok(path(slot))
+    }
+
+    fn schedule(&mut self, world: &World) {
+        let config = &world.config;
+        self.next_tick = world.grid.ticks + config.autosave_ticks;
+        self.next_time = match config.autosave_minutes {
+            0 => None,
+            minutes => Some(Instant::now() + Duration::from_secs(minutes * 60)),
+        };
+    }
+}
+
+// the world in the newest checkpoint in dir that can be loaded, damaged
+// checkpoints are passed over. The checkpoints are tried newest first so
+// that only the newest ones are parsed. Checkpoints saved close together
+// can have the same modified time so the world that has run longer wins a
+// tie.
+pub fn load_checkpoint(dir: &Path) -> Option<(World, PathBuf)> {
+    let mut checkpoints: Vec<(SystemTime, PathBuf)> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            name.starts_with("checkpoint-") && name.ends_with(".yaml")
+        })
+        .filter_map(|path| Some((modified(&path)?, path)))
+        .collect();
+    checkpoints.sort_by_key(|(time, _)| std::cmp::Reverse(*time));
+
+    for same_time in checkpoints.chunk_by(|a, b| a.0 == b.0) {
+        let newest = same_time
+            .iter()
+            .filter_map(|(_, path)| Some((load_world_file(path.to_str()?).ok()?, path.clone())))
+            .max_by_key(|(world, _)| world.grid.ticks);
+        if newest.is_some() {
+            return newest;
+        }
+    }
+    None
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
 pub fn load_world() -> World {
-    let file = File::open("world.yaml").unwrap();
-    let world: World = serde_yaml::from_reader(file).unwrap();
-    world
+    load_world_file("world.yaml").unwrap()
+}
+
+pub fn load_world_file(path: &str) -> Result<World, Box<dyn Error>> {
+    let file = File::open(path)?;
+    let world: World = serde_yaml::from_reader(file)?;
+    Ok(world)
 }
 
 impl Serialize for World {
@@ -38,8 +169,10 @@ impl Serialize for World {
         let mut s = serializer.serialize_struct("World", 1)?;
 
         // serialize the grid into a more human readable format -
-        // two vectors of coordinates, one for grass, one for creatures
-        let mut grasses: Vec<Coord> = Vec::new();
+        // vectors of coordinates, one each for grass, creatures, corpses and walls
+        let mut grasses: Vec<GrassSer> = Vec::new();
+        let mut corpses: Vec<CorpseSer> = Vec::new();
+        let mut walls: Vec<Coord> = Vec::new();
         let mut creatures: Vec<CreatureSer> = Vec::new();
         for x in 0..self.config.size as i32 {
             for y in 0..self.config.size as i32 {
@@ -50,10 +183,18 @@ impl Serialize for World {
                         let creature = self.creatures.get(&id).unwrap().clone();
                         creatures.push(CreatureSer { coord, creature });
                     }
-                    Cell::Grass => {
-                        grasses.push(coord);
+                    Cell::Grass(species, energy) => {
+                        grasses.push(GrassSer {
+                            coord,
+                            species,
+                            energy: Some(energy),
+                        });
+                    }
+                    Cell::Corpse(energy) => {
+                        corpses.push(CorpseSer { coord, energy });
                     }
-                    _ => {}
+                    Cell::Wall => walls.push(coord),
+                    Cell::Empty => {}
                 }
             }
         }
@@ -62,6 +203,8 @@ impl Serialize for World {
         s.serialize_field(FIELDS[1], &self.config)?;
         s.serialize_field(FIELDS[2], &creatures)?;
         s.serialize_field(FIELDS[3], &grasses)?;
+        s.serialize_field(FIELDS[4], &corpses)?;
+        s.serialize_field(FIELDS[5], &walls)?;
 
         s.end()
     }
@@ -79,6 +222,8 @@ impl<'de> Deserialize<'de> for World {
             Config,
             Creatures,
             Grasses,
+            Corpses,
+            Walls,
         }
 
         struct WorldSerVisitor;
@@ -97,7 +242,9 @@ impl<'de> Deserialize<'de> for World {
                 let mut config: Option<Settings> = None;
                 let mut grid: Option<WorldGrid> = None;
                 let mut creatures: Option<Vec<CreatureSer>> = None;
-                let mut grasses: Option<Vec<Coord>> = None;
+                let mut grasses: Option<Vec<GrassSer>> = None;
+                let mut corpses: Option<Vec<CorpseSer>> = None;
+                let mut walls: Option<Vec<Coord>> = None;
 
                 while let Some(key) = map.next_key()? {
                     match key {
@@ -105,6 +252,8 @@ impl<'de> Deserialize<'de> for World {
                         Field::Config => config = Some(map.next_value()?),
                         Field::Creatures => creatures = Some(map.next_value()?),
                         Field::Grasses => grasses = Some(map.next_value()?),
+                        Field::Corpses => corpses = Some(map.next_value()?),
+                        Field::Walls => walls = Some(map.next_value()?),
                     }
                 }
                 let config = config.ok_or_else(|| de::Error::missing_field("config"))?;
@@ -117,12 +266,28 @@ impl<'de> Deserialize<'de> for World {
                     creature.move_to(creature_coord.coord);
                     creature.set_tx(world.tx.clone());
                     creature.set_config(config.clone());
-                    world.tx.send(Update::AddEntity(creature)).unwrap();
+                    world
+                        .tx
+                        .send(Update::AddEntity(Box::new(creature)))
+                        .unwrap();
+                }
+                for grass in grasses.unwrap() {
+                    let plants = &world.config.plants;
+                    let species = grass.species.min(plants.len() as u8 - 1);
+                    let energy = grass.energy.unwrap_or(plants[species as usize].energy);
+                    world.grid.add_grass(grass.coord, species, energy);
+                }
+                // older save files have no corpses
+                for corpse in corpses.unwrap_or_default() {
+                    world.add_corpse(corpse.coord, corpse.energy);
                 }
-                for grass_coord in grasses.unwrap() {
-                    world.grid.add_grass(grass_coord);
+                // nor walls
+                for wall in walls.unwrap_or_default() {
+                    world.paint(wall, Brush::Wall);
                 }
                 world.apply_updates();
+                // the heatmaps are not saved and the loaded creatures are not births
+                world.grid.heatmaps = Heatmaps::new(config.size);
                 Ok(world)
             }
         }
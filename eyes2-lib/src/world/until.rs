@@ -0,0 +1,86 @@
+//! Conditions that stop a running world, used by the GUI's run until
+//! command and by headless runs.
+//!
+//! A condition is written as `population<N` (fewer than N creatures),
+//! `extinct=NAME` (no creatures of the genotype NAME are left) or
+//! `tick=T` (the world has reached tick T). NAME is the name of a genotype
+//! in the creatures setting, such as giles or scavenger.
+
+use super::*;
+use crate::settings::Settings;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Until {
+    Population(u64),
+    Extinct(String),
+    Tick(u64),
+}
+
+impl Until {
+    pub fn met(&self, world: &World) -> bool {
+        match self {
+            Until::Population(count) => world.creature_count() < *count,
+            Until::Extinct(name) => !world
+                .creatures()
+                .any(|c| c.genotype().describe().name == *name),
+            Until::Tick(tick) => world.grid.ticks >= *tick,
+        }
+    }
+
+    // an error if the condition can never be met by a world with these
+    // settings, i.e. it names a genotype the world does not have
+    pub fn check(&self, config: &Settings) -> Result<(), String> {
+        match self {
+            Until::Extinct(name) if !config.creatures.iter().any(|(n, _)| n == name) => {
+                let names: Vec<&str> = config.creatures.iter().map(|(n, _)| n.as_str()).collect();
+                Err(format!(
+                    "'{}' is not one of the creatures: {}",
+                    name,
+                    names.join(", ")
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl FromStr for Until {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Until, String> {
+        let number = |value: &str| {
+            value
+                .trim()
+                .parse::<u64>()
+                .map_err(|_| format!("'{}' is not a number", value.trim()))
+        };
+        let text = text.trim();
+        if let Some(count) = text.strip_prefix("population<") {
+            Ok(Until::Population(number(count)?))
+        } else if let Some(name) = text.strip_prefix("extinct=") {
+            match name.trim() {
+                "" => Err("extinct needs the name of a genotype".to_string()),
+                name => Ok(Until::Extinct(name.to_string())),
+            }
+        } else if let Some(tick) = text.strip_prefix("tick=") {
+            Ok(Until::Tick(number(tick)?))
+        } else {
+            Err(format!(
+                "'{}' is not population<N, extinct=NAME or tick=T",
+                text
+            ))
+        }
+    }
+}
+
+impl fmt::Display for Until {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Until::Population(count) => write!(f, "population<{}", count),
+            Until::Extinct(name) => write!(f, "extinct={}", name),
+            Until::Tick(tick) => write!(f, "tick={}", tick),
+        }
+    }
+}
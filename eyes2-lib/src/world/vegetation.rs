@@ -0,0 +1,218 @@
+//! Vegetation models which decide where new grass grows at each grass tick,
+//! plus the seasonal schedule that varies how often grass ticks happen.
+//!
+//! The world may hold several species of plant, each with its own energy,
+//! growth rate and visibility. A plant's energy regrows on every grass tick
+//! until it is fully grown.
+//!
+//! The model, its parameters and the plant species are chosen in Settings.
+
+use super::*;
+use crate::entity::Vision;
+use crate::utils;
+use direction::{Direction, Directions};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+// the rule used to grow new grass at each grass tick
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GrassModel {
+    // each grass cell seeds one neighbour in a rotating direction
+    Spread,
+    // grass_spawn new grass cells appear anywhere in the world
+    Random,
+    // each empty cell regrows with a chance proportional to its grass
+    // neighbours and to the free space left in the world
+    Logistic,
+    // grass_spawn new grass cells appear inside the fertile zones
+    Fertile,
+}
+
+// a species of plant that can grow in the world
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Plant {
+    pub name: String,
+    // the sigil used to render the plant
+    pub sigil: char,
+    // energy held by a fully grown plant
+    pub energy: i32,
+    // fraction of the full energy gained on each grass tick,
+    // new plants start with this much energy
+    pub growth: f32, // MAX 1.0
+    // chance that the plant shows up when a creature looks at it
+    pub visibility: f32, // MAX 1.0
+}
+
+impl ::std::default::Default for Plant {
+    fn default() -> Plant {
+        Plant {
+            name: "grass".to_string(),
+            sigil: 'o',
+            energy: 1000,
+            growth: 1.0,
+            visibility: 1.0,
+        }
+    }
+}
+
+// choose the centres of the fertile zones for a new world
+pub fn fertile_zones(config: &Settings, rng: &FastRng) -> Vec<Coord> {
+    (0..config.fertile_zones)
+        .map(|_| Coord {
+            x: rng.i32(0..config.size as i32),
+            y: rng.i32(0..config.size as i32),
+        })
+        .collect()
+}
+
+impl World {
+    // the multiplier applied to the grass growth rate at the current tick,
+    // this follows a sine wave with period season_length
+    pub fn season_factor(&self) -> f64 {
+        let amplitude = self.config.season_amplitude as f64;
+        let phase = self.grid.ticks as f64 / self.config.season_length as f64;
+        1.0 + amplitude * (2.0 * PI * phase).sin()
+    }
+
+    pub(super) fn ticks_per_grass(&self) -> u64 {
+        // ticks per grass growth is between 100 to 1,000,000 in inverse
+        // logarithmic proportion to grass_rate parameter of 1 - 100
+        let ticks = (101 - self.grid.grass_rate).pow(2) * 100;
+        // seasons speed up or slow down the growth
+        ((ticks as f64 / self.season_factor()) as u64).max(1)
+    }
+
+    pub(super) fn grow_grass(&mut self) {
+        self.ripen_plants();
+
+        let new_grass = match self.config.grass_model {
+            GrassModel::Spread => self.spread_grass(),
+            GrassModel::Random => self.random_grass(),
+            GrassModel::Logistic => self.logistic_grass(),
+            GrassModel::Fertile => self.fertile_grass(),
+        };
+
+        for (coord, species) in new_grass {
+            let energy = self.plant_growth(species);
+            self.grid.add_grass(coord, species, energy);
+        }
+        self.grid.season = self.season_factor() as f32;
+    }
+
+    // pick a plant species at random
+    pub fn random_species(&self) -> u8 {
+        self.rng.u8(0..self.config.plants.len() as u8)
+    }
+
+    // hide any plants in a creature's vision that it fails to spot
+    pub(super) fn conceal_plants(&self, mut vision: Vision) -> Vision {
+        for cell in vision.iter_mut() {
+            if let Cell::Grass(species, _) = *cell {
+                if self.rng.f32() >= self.config.plants[species as usize].visibility {
+                    *cell = Cell::Empty;
+                }
+            }
+        }
+        vision
+    }
+
+    // the energy a plant of the given species gains per grass tick
+    fn plant_growth(&self, species: u8) -> i32 {
+        let plant = &self.config.plants[species as usize];
+        ((plant.energy as f32 * plant.growth) as i32).max(1)
+    }
+
+    // regrow the energy of every plant towards its fully grown value
+    fn ripen_plants(&mut self) {
+        for x in 0..self.config.size as i32 {
+            for y in 0..self.config.size as i32 {
+                let coord = Coord::new(x, y);
+                if let Cell::Grass(species, energy) = self.grid.get_cell(coord) {
+                    let full = self.config.plants[species as usize].energy;
+                    if energy < full {
+                        let energy = (energy + self.plant_growth(species)).min(full);
+                        self.grid.set_cell(coord, Cell::Grass(species, energy));
+                    }
+                }
+            }
+        }
+    }
+
+    fn spread_grass(&self) -> Vec<(Coord, u8)> {
+        // walk through all the cells in the grid and grow grass adjacent to
+        // cells that already have grass (add_grass ignores cells off the grid)
+        let mut grow_dir = Direction::North;
+        let mut new_grass: Vec<(Coord, u8)> = Vec::new();
+
+        for x in 0..self.config.size as i32 {
+            for y in 0..self.config.size as i32 {
+                let coord = Coord::new(x, y);
+                if let Cell::Grass(species, _) = self.grid.get_cell(coord) {
+                    new_grass.push((coord + grow_dir.coord(), species));
+                    grow_dir = utils::rotate_direction(grow_dir);
+                }
+            }
+        }
+        new_grass
+    }
+
+    fn random_grass(&self) -> Vec<(Coord, u8)> {
+        let size = self.config.size as i32;
+        (0..self.config.grass_spawn)
+            .map(|_| {
+                let coord = Coord::new(self.rng.i32(0..size), self.rng.i32(0..size));
+                (coord, self.random_species())
+            })
+            .collect()
+    }
+
+    fn logistic_grass(&self) -> Vec<(Coord, u8)> {
+        let size = self.config.size as i32;
+        let free = 1.0 - self.grid.grass_count() as f32 / (size * size) as f32;
+        let mut new_grass: Vec<(Coord, u8)> = Vec::new();
+
+        for x in 0..size {
+            for y in 0..size {
+                let coord = Coord::new(x, y);
+                if let Cell::Empty = self.grid.get_cell(coord) {
+                    // new plants take the species of one of their neighbours
+                    let neighbours: Vec<u8> = Directions
+                        .into_iter()
+                        .filter_map(|d| match self.grid.get_cell(coord + d.coord()) {
+                            Cell::Grass(species, _) => Some(species),
+                            _ => None,
+                        })
+                        .collect();
+                    let chance = self.config.grass_regrowth * neighbours.len() as f32 / 8.0 * free;
+                    if self.rng.f32() < chance {
+                        let species = neighbours[self.rng.usize(0..neighbours.len())];
+                        new_grass.push((coord, species));
+                    }
+                }
+            }
+        }
+        new_grass
+    }
+
+    fn fertile_grass(&self) -> Vec<(Coord, u8)> {
+        let zones = &self.grid.fertile_zones;
+        let radius = self.config.fertile_radius as i32;
+        let mut new_grass: Vec<(Coord, u8)> = Vec::new();
+
+        if zones.is_empty() {
+            return new_grass;
+        }
+        for _ in 0..self.config.grass_spawn {
+            let centre = zones[self.rng.usize(0..zones.len())];
+            let offset = Coord::new(
+                self.rng.i32(-radius..=radius),
+                self.rng.i32(-radius..=radius),
+            );
+            if offset.x.pow(2) + offset.y.pow(2) <= radius.pow(2) {
+                new_grass.push((centre + offset, self.random_species()));
+            }
+        }
+        new_grass
+    }
+}
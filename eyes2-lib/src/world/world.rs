@@ -1,13 +1,13 @@
 use crate::entity::{look_world, new_genotype, Creature, Update};
 use crate::settings::Settings;
-use crate::utils;
-use direction::{Coord, Direction};
+use direction::{Coord, Directions};
 use fastrand::Rng as FastRng;
 use std::collections::HashMap;
 use std::rc::Rc;
 use std::sync::mpsc;
 
-use super::grid::{Cell, WorldGrid};
+use super::grid::{Cell, CellUpdate, SnapshotParts, WorldGrid};
+use super::heatmap::{Heatmap, Heatmaps, REGION};
 
 // a world is a 2D WorldGrid of Cell plus a HashMap of creatures and grass blocks
 pub struct World {
@@ -24,47 +24,91 @@ pub struct World {
     config: Settings,
     // track when we will next call grass tick
     next_grass_tick: u64,
+    // the locations of decaying corpses
+    corpses: Vec<Coord>,
+    // track when we will next spread the scent layer (if there is any scent)
+    next_scent_tick: u64,
+    has_scent: bool,
+    // the species found by the last clustering and when to cluster next
+    species: Vec<species::Species>,
+    next_species_id: u32,
+    next_species_tick: u64,
+    // the population time series and when to sample it next
+    stats: stats::StatsSeries,
+    next_stats_tick: u64,
+    next_heatmap_tick: u64,
+    // the best genomes seen, to restart the world with
+    seeds: Vec<restart::Seed>,
     // a random number generator
     rng: fastrand::Rng,
 }
 
 // public static methods
 impl World {
-    pub fn new(config: Settings, restarts: u64) -> World {
+    pub fn new(mut config: Settings, restarts: u64) -> World {
+        config.clamp_plants();
         // create a square 2d vector of empty cells
         let grid = WorldGrid::new(config.size, config.grass_rate, config.speed, restarts);
         // create a channel for passing updates to the world from the creatures
         let (tx_update, rx_update) = mpsc::channel::<Update>();
 
-        let world = World {
+        let rng = FastRng::new();
+        let mut grid = grid;
+        grid.fertile_zones = vegetation::fertile_zones(&config, &rng);
+        grid.plant_sigils = config.plants.iter().map(|p| p.sigil).collect();
+
+        World {
             grid,
             creatures: HashMap::<u64, Creature>::new(),
             rx: rx_update,
             tx: Rc::new(tx_update),
             config,
             next_grass_tick: 0,
-            rng: FastRng::new(),
-        };
-
-        world
+            corpses: Vec::new(),
+            next_scent_tick: 0,
+            has_scent: false,
+            species: Vec::new(),
+            next_species_id: 1,
+            next_species_tick: 0,
+            stats: stats::StatsSeries::new(),
+            next_stats_tick: 0,
+            next_heatmap_tick: 0,
+            seeds: Vec::new(),
+            rng,
+        }
     }
 
-    pub fn load(config: Settings, grid: WorldGrid) -> World {
+    pub fn load(mut config: Settings, mut grid: WorldGrid) -> World {
+        config.clamp_plants();
         // create a channel for passing updates to the world from the creatures
         let (tx_update, rx_update) = mpsc::channel::<Update>();
         let next_grass_tick = grid.ticks + grid.grass_rate;
+        grid.plant_sigils = config.plants.iter().map(|p| p.sigil).collect();
+        let rng = FastRng::new();
+        // older save files have no fertile zones
+        if grid.fertile_zones.is_empty() {
+            grid.fertile_zones = vegetation::fertile_zones(&config, &rng);
+        }
 
-        let world = World {
+        World {
             grid,
             creatures: HashMap::<u64, Creature>::new(),
             rx: rx_update,
             tx: Rc::new(tx_update),
             config,
             next_grass_tick,
-            rng: FastRng::new(),
-        };
-
-        world
+            corpses: Vec::new(),
+            next_scent_tick: 0,
+            has_scent: false,
+            species: Vec::new(),
+            next_species_id: 1,
+            next_species_tick: 0,
+            stats: stats::StatsSeries::new(),
+            next_stats_tick: 0,
+            next_heatmap_tick: 0,
+            seeds: Vec::new(),
+            rng,
+        }
     }
 }
 
@@ -78,12 +122,52 @@ impl World {
         self.creatures.len() as u64
     }
 
-    pub fn populate(&mut self) {
-        for _ in 0..self.config.grass_count as usize {
-            let x = self.rng.i32(0..self.config.size as i32 - 1);
-            let y = self.rng.i32(0..self.config.size as i32 - 1);
-            self.grid.add_grass(Coord { x, y });
+    pub fn creatures(&self) -> impl Iterator<Item = &Creature> {
+        self.creatures.values()
+    }
+
+    pub fn creature(&self, id: u64) -> Option<&Creature> {
+        self.creatures.get(&id)
+    }
+
+    // record the vision and actions of a creature for the debugger
+    pub fn set_traced(&mut self, id: u64, traced: bool) {
+        if let Some(creature) = self.creatures.get_mut(&id) {
+            creature.set_traced(traced);
+        }
+    }
+
+    // a snapshot of the grid for the GUI, with the energy of each creature
+    // if asked for (see WorldGrid::snapshot)
+    pub fn snapshot(&mut self, parts: SnapshotParts) -> (WorldGrid, CellUpdate) {
+        let (mut grid, cells) = self.grid.snapshot(parts);
+        if parts.energies {
+            grid.energies = self
+                .creatures
+                .iter()
+                .map(|(id, creature)| (*id, (creature.coord(), creature.energy())))
+                .collect();
         }
+        (grid, cells)
+    }
+
+    pub fn config(&self) -> &Settings {
+        &self.config
+    }
+
+    // apply the settings that are safe to change in a running world, the
+    // rest (such as size) are left until the next world is created
+    pub fn set_config(&mut self, config: &Settings) {
+        self.config = self.config.with_live_fields(config);
+        self.grid.speed = self.config.speed;
+        self.grid.grass_rate = self.config.grass_rate;
+        for creature in self.creatures.values_mut() {
+            creature.set_config(self.config.clone());
+        }
+    }
+
+    pub fn populate(&mut self) {
+        self.plant_grass();
         for creature in self.config.creatures.iter() {
             for _ in 0..creature.1 {
                 let x = self.rng.i32(0..self.config.size as i32);
@@ -97,11 +181,35 @@ impl World {
                     self.config.clone(),
                     self.tx.clone(),
                 );
-                self.tx.send(Update::AddEntity(creature)).unwrap();
+                self.tx.send(Update::AddEntity(Box::new(creature))).unwrap();
             }
         }
 
         self.apply_updates();
+        // the initial creatures are not births
+        self.grid.heatmaps = Heatmaps::new(self.config.size);
+    }
+
+    // add a creature from outside the world (e.g. a creature file) at a
+    // random place, it is skipped if that place is taken
+    pub fn introduce(&mut self, mut creature: Creature) {
+        let x = self.rng.i32(0..self.config.size as i32);
+        let y = self.rng.i32(0..self.config.size as i32);
+        creature.move_to(Coord { x, y });
+        creature.set_tx(self.tx.clone());
+        creature.set_config(self.config.clone());
+        self.tx.send(Update::AddEntity(Box::new(creature))).unwrap();
+        self.apply_updates();
+    }
+
+    fn plant_grass(&mut self) {
+        for _ in 0..self.config.grass_count as usize {
+            let x = self.rng.i32(0..self.config.size as i32 - 1);
+            let y = self.rng.i32(0..self.config.size as i32 - 1);
+            let species = self.random_species();
+            let energy = self.config.plants[species as usize].energy;
+            self.grid.add_grass(Coord { x, y }, species, energy);
+        }
     }
 
     pub fn tick(&mut self) {
@@ -110,6 +218,30 @@ impl World {
             creature.tick();
         }
 
+        if !self.corpses.is_empty() {
+            self.decay_corpses();
+        }
+
+        if self.has_scent && self.grid.ticks >= self.next_scent_tick {
+            self.spread_scent();
+            self.next_scent_tick = self.grid.ticks + self.config.scent_interval;
+        }
+
+        if self.config.species_interval > 0 && self.grid.ticks >= self.next_species_tick {
+            self.cluster_species();
+            self.next_species_tick = self.grid.ticks + self.config.species_interval;
+        }
+
+        if self.config.stats_interval > 0 && self.grid.ticks >= self.next_stats_tick {
+            self.sample_stats();
+            self.next_stats_tick = self.grid.ticks + self.config.stats_interval;
+        }
+
+        if self.config.heatmap_interval > 0 && self.grid.ticks >= self.next_heatmap_tick {
+            self.sample_heatmaps();
+            self.next_heatmap_tick = self.grid.ticks + self.config.heatmap_interval;
+        }
+
         // limit calls to grass tick relative to grass_rate
         if self.grid.ticks >= self.next_grass_tick {
             self.grow_grass();
@@ -117,7 +249,7 @@ impl World {
         }
 
         self.apply_updates();
-        self.grid.ticks += 1;
+        self.inject_below_threshold();
     }
 }
 
@@ -133,38 +265,68 @@ impl World {
                     let coord = creature.coord();
                     let mut id = self.get_next_id();
                     let sigil = creature.get_sigil();
+                    let diet = creature.diet();
                     creature.set_id(id);
+                    creature.set_born(self.grid.ticks);
                     id = creature.id();
                     let cell = self.grid.get_cell(coord);
                     // Maybe a better way to do this but I wanted to try closures!
                     let add_creature = || {
-                        self.creatures.insert(id, creature);
+                        self.creatures.insert(id, *creature);
                         self.grid.creature_count = self.creature_count();
                     };
                     match cell {
                         Cell::Empty => add_creature(),
-                        Cell::Grass => {
+                        Cell::Grass(_, energy) if diet.eats_plants() => {
+                            add_creature();
+                            self.eat_grass(coord, id, energy);
+                        }
+                        Cell::Corpse(energy) if diet.eats_corpses() => {
                             add_creature();
-                            self.eat_grass(coord, id);
+                            self.eat_corpse(id, energy);
                         }
+                        // creatures that do not eat plants trample them
+                        Cell::Grass(_, _) => {
+                            add_creature();
+                            self.grid.remove_grass(coord);
+                        }
+                        // skip add if there is a corpse the creature does not eat
+                        Cell::Corpse(_) => continue,
                         // skip add if there is already a creature in the cell
                         Cell::Entity(_, _) => continue,
                         Cell::Wall => continue,
                     };
                     self.grid.set_cell(coord, Cell::Entity(id, sigil));
+                    self.grid.heatmaps.add(Heatmap::Births, coord, 1.0);
                 }
                 Update::RemoveEntity(id, coord) => {
                     self.validate_creature(id, coord);
-                    self.creatures.remove(&id);
+                    let mut remaining = 0;
+                    if let Some(creature) = self.creatures.remove(&id) {
+                        self.record_seed(&creature);
+                        remaining = creature.energy().max(0);
+                    }
                     self.grid.creature_count = self.creature_count();
                     self.grid.set_cell(coord, Cell::Empty);
+                    self.grid.heatmaps.add(Heatmap::Deaths, coord, 1.0);
+                    if self.config.corpse_energy > 0 {
+                        self.add_corpse(coord, self.config.corpse_energy + remaining);
+                    }
                 }
                 Update::MoveEntity(id, old_coord, new_coord) => {
                     self.validate_creature(id, old_coord);
                     let cell = self.grid.get_cell(new_coord);
+                    let diet = self.creatures[&id].diet();
                     match cell {
                         Cell::Empty => {}
-                        Cell::Grass => self.eat_grass(new_coord, id),
+                        Cell::Grass(_, energy) if diet.eats_plants() => {
+                            self.eat_grass(new_coord, id, energy)
+                        }
+                        Cell::Corpse(energy) if diet.eats_corpses() => self.eat_corpse(id, energy),
+                        // creatures that do not eat plants trample them
+                        Cell::Grass(_, _) => self.grid.remove_grass(new_coord),
+                        // skip move if there is a corpse the creature does not eat
+                        Cell::Corpse(_) => continue,
                         // skip move if there is already a creature in the cell
                         // TODO this needs to change for carnivores
                         Cell::Entity(_, _) => continue,
@@ -175,16 +337,59 @@ impl World {
                     self.grid.set_cell(old_coord, Cell::Empty);
                     self.grid
                         .set_cell(new_coord, Cell::Entity(id, creature.get_sigil()));
+                    self.grid.heatmaps.add(Heatmap::Visits, new_coord, 1.0);
                 }
                 Update::Look(id) => {
-                    let creature = self.creatures.get_mut(&id).unwrap();
-                    let coord = creature.coord();
-                    let vision = look_world(coord, &self.grid);
+                    let coord = self.creatures[&id].coord();
+                    let (vision, scent) = look_world(coord, &self.grid);
+                    let vision = self.conceal_plants(vision);
                     // Send the list of adjacent cells back to the requesting creature
-                    creature.vision(vision);
+                    let creature = self.creatures.get_mut(&id).unwrap();
+                    creature.vision(vision, scent);
+                }
+                Update::Scent(coord, amount) => self.add_scent(coord, amount),
+                Update::Signal(id, coord, value) => self.broadcast_signal(id, coord, value),
+                Update::Mate(id) => {
+                    if self.config.mating {
+                        self.mate(id)
+                    }
+                }
+            }
+        }
+    }
+
+    // add the grass to the grass heatmap and work out the mean energy of
+    // the creatures in each region
+    fn sample_heatmaps(&mut self) {
+        let size = self.config.size as i32;
+        for y in 0..size {
+            for x in 0..size {
+                let coord = Coord { x, y };
+                if let Cell::Grass(_, _) = self.grid.get_cell(coord) {
+                    self.grid.heatmaps.add(Heatmap::Grass, coord, 1.0);
                 }
             }
         }
+
+        let regions = ((size + REGION - 1) / REGION) as usize;
+        let mut totals = vec![(0.0, 0); regions.pow(2)];
+        let region =
+            |coord: Coord| (coord.x / REGION) as usize + (coord.y / REGION) as usize * regions;
+        for creature in self.creatures.values() {
+            let total = &mut totals[region(creature.coord())];
+            total.0 += creature.energy() as f32;
+            total.1 += 1;
+        }
+        let mut energy = vec![0.0; (size * size) as usize];
+        for y in 0..size {
+            for x in 0..size {
+                let (sum, count) = totals[region(Coord { x, y })];
+                if count > 0 {
+                    energy[(x + y * size) as usize] = sum / count as f32;
+                }
+            }
+        }
+        self.grid.heatmaps.set_layer(Heatmap::Energy, energy);
     }
 
     fn get_next_id(&mut self) -> u64 {
@@ -206,43 +411,93 @@ impl World {
         };
     }
 
-    fn ticks_per_grass(&self) -> u64 {
-        // ticks per grass growth is between 100 to 1,000,000 in inverse
-        // logarithmic proportion to grass_rate parameter of 1 - 100
-        (101 - self.grid.grass_rate as u64).pow(2) * 100
+    fn eat_grass(&mut self, coord: Coord, id: u64, energy: i32) {
+        self.grid.remove_grass(coord);
+        self.creatures.get_mut(&id).unwrap().eat(energy);
     }
 
-    fn grow_grass(&mut self) {
-        // walk through all the cells in the grid except the edges and grow grass
-        // adjacent to cells that already have grass
-        let mut grow_dir = Direction::North;
-        let mut new_grass: Vec<Coord> = Vec::new();
+    fn mate(&mut self, id: u64) {
+        // the creature may have died earlier in this tick
+        let Some(creature) = self.creatures.get(&id) else {
+            return;
+        };
+        let mate_energy = self.config.mate_energy;
+        if creature.energy() < mate_energy {
+            return;
+        }
+        let coord = creature.coord();
+
+        // find a compatible partner and an empty cell for the child
+        let mut partner = None;
+        let mut nursery = None;
+        for direction in Directions {
+            let next = coord + direction.coord();
+            match self.grid.get_cell(next) {
+                Cell::Entity(other, _)
+                    if partner.is_none() && self.creatures[&other].energy() >= mate_energy =>
+                {
+                    if let Some(genotype) = creature.crossover(&self.creatures[&other]) {
+                        partner = Some((other, genotype));
+                    }
+                }
+                Cell::Empty if nursery.is_none() => nursery = Some(next),
+                _ => {}
+            }
+        }
+        let (Some((other, genotype)), Some(nursery)) = (partner, nursery) else {
+            return;
+        };
+
+        let mut partner = self.creatures.remove(&other).unwrap();
+        let creature = self.creatures.get_mut(&id).unwrap();
+        let child = creature.mate(&mut partner, genotype, nursery);
+        self.creatures.insert(other, partner);
+        self.tx.send(Update::AddEntity(Box::new(child))).unwrap();
+    }
 
-        for x in 1..self.config.size as i32 - 2 {
-            for y in 1..self.config.size as i32 - 2 {
-                let coord = Coord::new(x, y);
-                let cell = self.grid.get_cell(coord);
-                match cell {
-                    Cell::Grass => {
-                        new_grass.push(coord + grow_dir.coord());
-                        grow_dir = utils::rotate_direction(grow_dir);
+    fn broadcast_signal(&mut self, id: u64, coord: Coord, value: u8) {
+        let radius = self.config.signal_radius as i32;
+        for x in coord.x - radius..=coord.x + radius {
+            for y in coord.y - radius..=coord.y + radius {
+                if let Cell::Entity(receiver, _) = self.grid.get_cell(Coord { x, y }) {
+                    if receiver != id {
+                        self.creatures
+                            .get_mut(&receiver)
+                            .unwrap()
+                            .signal(value, coord);
                     }
-                    _ => {}
                 }
             }
         }
+    }
 
-        for coord in new_grass {
-            self.grid.add_grass(coord);
+    fn add_corpse(&mut self, coord: Coord, energy: i32) {
+        if let Cell::Empty = self.grid.get_cell(coord) {
+            self.grid.set_cell(coord, Cell::Corpse(energy));
+            self.corpses.push(coord);
         }
     }
 
-    fn eat_grass(&mut self, coord: Coord, id: u64) {
-        self.grid.remove_grass(coord);
-        self.creatures
-            .get_mut(&id)
-            .unwrap()
-            .eat(self.config.grass_energy);
+    fn eat_corpse(&mut self, id: u64, energy: i32) {
+        // the corpse cell is replaced by the creature that ate it
+        self.creatures.get_mut(&id).unwrap().eat(energy);
+    }
+
+    fn decay_corpses(&mut self) {
+        let decay = self.config.corpse_decay;
+        let grid = &mut self.grid;
+        self.corpses.retain(|&coord| match grid.get_cell(coord) {
+            Cell::Corpse(energy) if energy > decay => {
+                grid.set_cell(coord, Cell::Corpse(energy - decay));
+                true
+            }
+            Cell::Corpse(_) => {
+                grid.set_cell(coord, Cell::Empty);
+                false
+            }
+            // the corpse has already been eaten
+            _ => false,
+        });
     }
 }
 
@@ -252,3 +507,24 @@ mod test;
 
 #[path = "store.rs"]
 pub mod store;
+
+#[path = "vegetation.rs"]
+pub mod vegetation;
+
+#[path = "scent.rs"]
+mod scent;
+
+#[path = "edit.rs"]
+pub mod edit;
+
+#[path = "species.rs"]
+mod species;
+
+#[path = "stats.rs"]
+pub mod stats;
+
+#[path = "until.rs"]
+pub mod until;
+
+#[path = "restart.rs"]
+pub mod restart;
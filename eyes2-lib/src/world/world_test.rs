@@ -1,13 +1,14 @@
 use super::*;
 
+use crate::entity::{Genotype, GenotypeActions};
 use crate::settings::Settings;
+use crate::world::{write_stats_csv, CellUpdate, GrassModel, Heatmap, RestartPolicy, Until};
 
 fn get_config() -> Settings {
     Settings {
         size: 40,
         grass_count: 10,
         grass_rate: 5000,
-        grass_energy: 1000,
         creature_move_energy: 100,
         creature_idle_energy: 1,
         creature_move_rate: 0.01,
@@ -44,5 +45,686 @@ fn check_populate() {
     let _creature_count = world.creature_count();
     world.creatures.remove(&1);
 
-    assert_eq!(world.creature_count(), _creature_count - 1 as u64);
+    assert_eq!(world.creature_count(), _creature_count - 1);
+}
+
+#[test]
+fn check_spread_grass_reaches_edges() {
+    let mut world = World::new(get_config(), 0);
+    let corner = Coord { x: 39, y: 39 };
+    world.grid.add_grass(corner, 0, 1000);
+
+    world.grow_grass();
+
+    // the rotating spread rule seeds north first, which stays on the grid
+    assert_eq!(world.grid.grass_count(), 2);
+    assert!(matches!(
+        world.grid.get_cell(Coord { x: 39, y: 38 }),
+        Cell::Grass(0, _)
+    ));
+}
+
+#[test]
+fn check_fertile_grass_stays_in_zones() {
+    let config = Settings {
+        grass_model: GrassModel::Fertile,
+        grass_spawn: 100,
+        fertile_zones: 1,
+        fertile_radius: 3,
+        ..get_config()
+    };
+    let mut world = World::new(config, 0);
+    let centre = world.grid.fertile_zones[0];
+
+    world.grow_grass();
+
+    assert!(world.grid.grass_count() > 0);
+    for x in 0..40 {
+        for y in 0..40 {
+            if let Cell::Grass(_, _) = world.grid.get_cell(Coord { x, y }) {
+                assert!((x - centre.x).pow(2) + (y - centre.y).pow(2) <= 9);
+            }
+        }
+    }
+}
+
+#[test]
+fn check_seasons_vary_growth() {
+    let config = Settings {
+        grass_rate: 50,
+        season_amplitude: 0.5,
+        season_length: 1000,
+        ..get_config()
+    };
+    let mut world = World::new(config, 0);
+    let equinox = world.ticks_per_grass();
+
+    world.grid.ticks = 250;
+    assert!((world.season_factor() - 1.5).abs() < 1e-9);
+    assert!(world.ticks_per_grass() < equinox);
+
+    world.grid.ticks = 750;
+    assert!((world.season_factor() - 0.5).abs() < 1e-9);
+    assert!(world.ticks_per_grass() > equinox);
+}
+
+#[test]
+fn check_fertile_zones_are_loaded() {
+    let config = Settings {
+        grass_model: GrassModel::Fertile,
+        fertile_zones: 3,
+        ..get_config()
+    };
+    let mut world = World::new(config, 0);
+    let text = serde_yaml::to_string(&world).unwrap();
+    let loaded: World = serde_yaml::from_str(&text).unwrap();
+    assert_eq!(loaded.grid.fertile_zones, world.grid.fertile_zones);
+
+    // a save from before the fertile model gets new zones
+    world.grid.fertile_zones.clear();
+    let text = serde_yaml::to_string(&world).unwrap();
+    let loaded: World = serde_yaml::from_str(&text).unwrap();
+    assert_eq!(loaded.grid.fertile_zones.len(), 3);
+}
+
+#[test]
+fn check_creatures_keep_their_age_when_loaded() {
+    let config = Settings {
+        creatures: vec![("noop".to_string(), 1)],
+        grass_rate: 50,
+        ..get_config()
+    };
+    let mut world = World::new(config, 0);
+    world.populate();
+    for _ in 0..5 {
+        world.tick();
+    }
+    // introduce skips a cell that is already taken, so retry until it lands
+    while world.creatures().count() < 2 {
+        let genotype = new_genotype("noop", world.config.clone()).unwrap();
+        world.introduce(Creature::detached(genotype, world.config.clone()));
+    }
+    let mut born: Vec<u64> = world.creatures().map(|c| c.born()).collect();
+    born.sort();
+    assert_eq!(born, [0, world.grid.ticks]);
+
+    let text = serde_yaml::to_string(&world).unwrap();
+    let mut loaded: World = serde_yaml::from_str(&text).unwrap();
+    loaded.tick();
+    let mut loaded_born: Vec<u64> = loaded.creatures().map(|c| c.born()).collect();
+    loaded_born.sort();
+    assert_eq!(loaded_born, born);
+}
+
+#[test]
+fn check_plants_ripen() {
+    let mut world = World::new(get_config(), 0);
+    // berries gain a fifth of their full 3000 energy each grass tick
+    let coord = Coord { x: 5, y: 5 };
+    world.grid.add_grass(coord, 1, 600);
+
+    world.grow_grass();
+    assert!(matches!(world.grid.get_cell(coord), Cell::Grass(1, 1200)));
+
+    world.grid.set_cell(coord, Cell::Grass(1, 2900));
+    world.grow_grass();
+    assert!(matches!(world.grid.get_cell(coord), Cell::Grass(1, 3000)));
+}
+
+#[test]
+fn check_corpses_decay_and_are_scavenged() {
+    let config = Settings {
+        corpse_energy: 500,
+        corpse_decay: 100,
+        ..get_config()
+    };
+    let mut world = World::new(config.clone(), 0);
+    let corpse = Coord { x: 5, y: 5 };
+    world.add_corpse(corpse, 150);
+    world.decay_corpses();
+    assert!(matches!(world.grid.get_cell(corpse), Cell::Corpse(50)));
+    world.decay_corpses();
+    assert!(matches!(world.grid.get_cell(corpse), Cell::Empty));
+
+    // a herbivore cannot move onto a corpse but a scavenger eats it
+    world.add_corpse(corpse, 500);
+    for (which, coord) in [
+        ("noop", Coord { x: 4, y: 5 }),
+        ("scavenger", Coord { x: 6, y: 5 }),
+    ] {
+        let genotype = new_genotype(which, config.clone()).unwrap();
+        let creature = Creature::new(genotype, coord, config.clone(), world.tx.clone());
+        world
+            .tx
+            .send(Update::AddEntity(Box::new(creature)))
+            .unwrap();
+    }
+    world.apply_updates();
+    world
+        .tx
+        .send(Update::MoveEntity(1, Coord { x: 4, y: 5 }, corpse))
+        .unwrap();
+    world.apply_updates();
+    assert!(matches!(world.grid.get_cell(corpse), Cell::Corpse(500)));
+
+    world
+        .tx
+        .send(Update::MoveEntity(2, Coord { x: 6, y: 5 }, corpse))
+        .unwrap();
+    world.apply_updates();
+    assert!(matches!(world.grid.get_cell(corpse), Cell::Entity(2, 'S')));
+    world.decay_corpses();
+    assert!(world.corpses.is_empty());
+
+    // a scavenger tramples the grass next to a corpse without eating it
+    let grass = Coord { x: 5, y: 6 };
+    world.grid.add_grass(grass, 0, 1000);
+    let energy = world.creatures[&2].energy();
+    world.tx.send(Update::MoveEntity(2, corpse, grass)).unwrap();
+    world.apply_updates();
+    assert!(matches!(world.grid.get_cell(grass), Cell::Entity(2, 'S')));
+    assert_eq!(world.grid.grass_count(), 0);
+    assert_eq!(world.creatures[&2].energy(), energy);
+
+    // and its corpse keeps the energy it had left
+    world.tx.send(Update::RemoveEntity(2, grass)).unwrap();
+    world.apply_updates();
+    assert!(matches!(world.grid.get_cell(grass), Cell::Corpse(e) if e == 500 + energy));
+}
+
+#[test]
+fn check_scent_spreads_and_evaporates() {
+    let config = Settings {
+        scent_diffusion: 0.8,
+        scent_evaporation: 0.5,
+        ..get_config()
+    };
+    let mut world = World::new(config, 0);
+    let centre = Coord { x: 5, y: 5 };
+    world.tx.send(Update::Scent(centre, 100.0)).unwrap();
+    world.apply_updates();
+
+    world.spread_scent();
+    assert!((world.grid.get_scent(centre) - 10.0).abs() < 1e-4);
+    let (_, scent) = look_world(centre, &world.grid);
+    assert!(scent.iter().all(|s| (s - 5.0).abs() < 1e-4));
+    // and the overlay shows it
+    assert_eq!(
+        world.grid.heatmaps.get(Heatmap::Scent, centre),
+        world.grid.get_scent(centre)
+    );
+
+    // scent eventually evaporates completely
+    for _ in 0..20 {
+        world.spread_scent();
+    }
+    assert!(!world.has_scent);
+    assert_eq!(world.grid.get_scent(centre), 0.0);
+}
+
+// a genotype that leaves the same amount of scent every tick
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+struct Scenter(f32);
+
+#[typetag::serde(name = "test_scenter")]
+impl Genotype for Scenter {
+    fn tick(&mut self) -> GenotypeActions {
+        GenotypeActions::Scent(self.0)
+    }
+
+    fn set_energy(&mut self, _energy: i32) {}
+}
+
+#[test]
+fn check_scent_amounts_are_limited() {
+    let config = Settings {
+        scent_energy: 7,
+        scent_max: 50.0,
+        creature_idle_energy: 0,
+        grass_rate: 50,
+        ..get_config()
+    };
+    let mut world = World::new(config.clone(), 0);
+    let amounts = [f32::NAN, f32::INFINITY, -5.0, 1000.0];
+    for (x, amount) in amounts.iter().enumerate() {
+        let coord = Coord {
+            x: x as i32 * 5,
+            y: 5,
+        };
+        let creature = Creature::new(
+            Box::new(Scenter(*amount)),
+            coord,
+            config.clone(),
+            world.tx.clone(),
+        );
+        world
+            .tx
+            .send(Update::AddEntity(Box::new(creature)))
+            .unwrap();
+    }
+    world.apply_updates();
+    let energies: Vec<i32> = (1..=4).map(|id| world.creatures[&id].energy()).collect();
+
+    world.tick();
+
+    // only the last creature is charged, for at most scent_max
+    for id in 1..=4 {
+        let charge = energies[id as usize - 1] - world.creatures[&id].energy();
+        assert_eq!(charge, if id == 4 { 7 } else { 0 });
+    }
+    assert_eq!(world.grid.get_scent(Coord { x: 15, y: 5 }), 50.0);
+    assert_eq!(world.grid.get_scent(Coord { x: 10, y: 5 }), 0.0);
+}
+
+// a genotype that records the signals it hears
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+struct Listener {
+    #[serde(skip)]
+    heard: Rc<std::cell::RefCell<Vec<(u8, Coord)>>>,
+}
+
+#[typetag::serde(name = "test_listener")]
+impl Genotype for Listener {
+    fn tick(&mut self) -> GenotypeActions {
+        GenotypeActions::None
+    }
+
+    fn set_energy(&mut self, _energy: i32) {}
+
+    fn signal(&mut self, value: u8, offset: Coord) {
+        self.heard.borrow_mut().push((value, offset));
+    }
+}
+
+#[test]
+fn check_signals_reach_creatures_in_radius() {
+    let config = Settings {
+        signal_radius: 2,
+        ..get_config()
+    };
+    let mut world = World::new(config.clone(), 0);
+    let listener = Listener::default();
+    for coord in [
+        Coord { x: 10, y: 10 },
+        Coord { x: 12, y: 9 },
+        Coord { x: 13, y: 10 },
+    ] {
+        let creature = Creature::new(
+            Box::new(listener.clone()),
+            coord,
+            config.clone(),
+            world.tx.clone(),
+        );
+        world
+            .tx
+            .send(Update::AddEntity(Box::new(creature)))
+            .unwrap();
+    }
+    world.apply_updates();
+
+    world
+        .tx
+        .send(Update::Signal(1, Coord { x: 10, y: 10 }, 7))
+        .unwrap();
+    world.apply_updates();
+
+    // only the creature within two cells hears the signal, the sender does not
+    assert_eq!(*listener.heard.borrow(), vec![(7, Coord { x: -2, y: 1 })]);
+}
+
+#[test]
+fn check_mating_needs_compatible_partner() {
+    let config = get_config();
+    let mut world = World::new(config.clone(), 0);
+    let spots = [
+        ("neural", Coord { x: 10, y: 10 }),
+        ("neural", Coord { x: 11, y: 10 }),
+        ("noop", Coord { x: 20, y: 20 }),
+        ("neural", Coord { x: 21, y: 20 }),
+    ];
+    for (which, coord) in spots {
+        let genotype = new_genotype(which, config.clone()).unwrap();
+        let creature = Creature::new(genotype, coord, config.clone(), world.tx.clone());
+        world
+            .tx
+            .send(Update::AddEntity(Box::new(creature)))
+            .unwrap();
+    }
+    world.apply_updates();
+    assert_eq!(world.creature_count(), 4);
+
+    // a noop genotype cannot mate with a neural one
+    world.tx.send(Update::Mate(3)).unwrap();
+    world.apply_updates();
+    assert_eq!(world.creature_count(), 4);
+
+    let parent_energy = world.creatures[&1].energy() + world.creatures[&2].energy();
+    world.tx.send(Update::Mate(1)).unwrap();
+    world.apply_updates();
+    assert_eq!(world.creature_count(), 5);
+
+    // the child's energy came from its parents
+    let total: i32 = [1, 2, 5]
+        .iter()
+        .map(|id| world.creatures[id].energy())
+        .sum();
+    assert_eq!(total, parent_energy);
+
+    // creatures without mate_energy cannot mate
+    world.config.mate_energy = world.creatures[&1].energy() + 1;
+    world.tx.send(Update::Mate(1)).unwrap();
+    world.apply_updates();
+    assert_eq!(world.creature_count(), 5);
+}
+
+#[test]
+fn check_species_cluster_by_genome() {
+    let config = Settings {
+        size: 20,
+        grass_count: 0,
+        creatures: vec![("noop".to_string(), 5), ("random".to_string(), 5)],
+        species_interval: 1,
+        ..Settings::default()
+    };
+    let mut world = World::new(config, 0);
+    world.populate();
+    world.tick();
+
+    // identical genomes of each genotype make one species each
+    assert_eq!(world.grid.species.len(), 2);
+    // (creatures that landed on the same cell were not added)
+    let members: usize = world.grid.species.iter().map(|s| s.size).sum();
+    assert_eq!(members, world.creature_count() as usize);
+    assert_eq!(world.grid.species_of.len(), members);
+}
+
+#[test]
+fn check_diversity_stats() {
+    let config = Settings {
+        size: 40,
+        grass_count: 0,
+        creatures: vec![("noop".to_string(), 5), ("giles".to_string(), 5)],
+        stats_interval: 1,
+        ..Settings::default()
+    };
+    let mut world = World::new(config, 0);
+    world.populate();
+    world.tick();
+
+    let sample = world.stats().back().unwrap();
+    let (giles, noop) = (&sample.genotypes[0], &sample.genotypes[1]);
+    assert_eq!((giles.name.as_str(), noop.name.as_str()), ("giles", "noop"));
+
+    // the noop creatures all have the same (empty) genome
+    assert_eq!(noop.distinct, 1);
+    assert_eq!(noop.entropy, 0.0);
+    assert_eq!(noop.mean_distance, 0.0);
+
+    // every giles creature has its own random genome
+    assert_eq!(giles.distinct, giles.count);
+    assert!((giles.entropy - (giles.count as f32).log2()).abs() < 0.001);
+    assert!(giles.mean_distance > 0.5);
+
+    let mut csv = Vec::new();
+    write_stats_csv(world.stats(), &mut csv).unwrap();
+    assert_eq!(String::from_utf8(csv).unwrap().lines().count(), 3);
+}
+
+#[test]
+fn check_heatmaps_record_deaths() {
+    let config = Settings {
+        size: 20,
+        grass_count: 0,
+        creatures: vec![("noop".to_string(), 10)],
+        creature_initial_energy: (1, 2),
+        creature_idle_energy: 10,
+        heatmap_interval: 1,
+        ..Settings::default()
+    };
+    let mut world = World::new(config, 0);
+    world.populate();
+    let count = world.creature_count();
+    let coords: Vec<Coord> = world.creatures().map(|c| c.coord()).collect();
+
+    world.tick();
+    assert_eq!(world.creature_count(), 0);
+    let heatmaps = &world.grid.heatmaps;
+    let deaths: f32 = coords
+        .iter()
+        .map(|c| heatmaps.get(Heatmap::Deaths, *c))
+        .sum();
+    assert_eq!(deaths, count as f32);
+    // the initial creatures are not births
+    assert_eq!(heatmaps.max(Heatmap::Births), 0.0);
+}
+
+#[test]
+fn check_painted_walls_are_saved() {
+    let mut world = World::new(get_config(), 0);
+    let (wall, grass) = (Coord { x: 3, y: 4 }, Coord { x: 5, y: 5 });
+    world.paint(wall, edit::Brush::Wall);
+    world.paint(grass, edit::Brush::Grass);
+    world.paint(grass, edit::Brush::Grass);
+    assert_eq!(world.grid.grass_count(), 1);
+    // painting off the grid does nothing
+    world.paint(Coord { x: -1, y: 40 }, edit::Brush::Wall);
+
+    let text = serde_yaml::to_string(&world).unwrap();
+    let mut loaded: World = serde_yaml::from_str(&text).unwrap();
+    assert!(matches!(loaded.grid.get_cell(wall), Cell::Wall));
+    assert_eq!(loaded.grid.grass_count(), 1);
+
+    loaded.paint(grass, edit::Brush::Erase);
+    loaded.paint(wall, edit::Brush::Erase);
+    assert_eq!(loaded.grid.grass_count(), 0);
+    assert!(matches!(loaded.grid.get_cell(wall), Cell::Empty));
+
+    // a corpse that is painted over stops decaying
+    for brush in edit::Brush::ALL {
+        loaded.paint(wall, edit::Brush::Erase);
+        loaded.add_corpse(wall, 100);
+        assert_eq!(loaded.corpses, [wall]);
+        loaded.paint(wall, brush);
+        assert!(loaded.corpses.is_empty());
+    }
+}
+
+#[test]
+fn check_snapshots_send_changed_cells() {
+    let mut world = World::new(get_config(), 0);
+    let (_, first) = world.grid.snapshot(SnapshotParts::default());
+    assert!(matches!(first, CellUpdate::Full(_)));
+    let mut gui = world.grid.snapshot(SnapshotParts::default()).0;
+    gui.apply_update(None, first);
+
+    let (grass, wall) = (Coord { x: 2, y: 0 }, Coord { x: 0, y: 1 });
+    world.grid.add_grass(grass, 0, 1000);
+    // only the look of a cell matters so new energy is not a change
+    world.grid.set_cell(grass, Cell::Grass(0, 500));
+    world.paint(wall, edit::Brush::Wall);
+    let (snapshot, update) = world.grid.snapshot(SnapshotParts::default());
+    match &update {
+        CellUpdate::Changed(changes) => {
+            let indices: Vec<usize> = changes.iter().map(|(i, _)| *i).collect();
+            assert_eq!(indices, [2, 40]);
+        }
+        CellUpdate::Full(_) => panic!("expected only the changed cells"),
+    }
+
+    let mut next = snapshot;
+    next.apply_update(Some(gui), update);
+    assert!(matches!(next.get_cell(wall), Cell::Wall));
+    assert!(matches!(next.get_cell(grass), Cell::Grass(0, 500)));
+    assert!(
+        matches!(world.grid.snapshot(SnapshotParts::default()).1, CellUpdate::Changed(c) if c.is_empty())
+    );
+}
+
+#[test]
+fn check_snapshot_size_is_independent_of_world_size() {
+    // the snapshot after the first holds only what changed, its debug
+    // text would hold every cell of the heatmaps if they were sent
+    let snapshot = |size: u16, parts: SnapshotParts| {
+        let config = Settings {
+            size,
+            grass_count: 0,
+            creatures: vec![("noop".to_string(), 1)],
+            ..get_config()
+        };
+        let mut world = World::new(config, 0);
+        world.populate();
+        world.snapshot(parts);
+        format!("{:?}", world.snapshot(parts)).len()
+    };
+
+    let small = snapshot(20, SnapshotParts::default());
+    let big = snapshot(250, SnapshotParts::default());
+    assert!(big < small + 100, "{} bytes grew to {}", small, big);
+
+    let heatmaps = SnapshotParts {
+        heatmaps: true,
+        ..SnapshotParts::default()
+    };
+    assert!(snapshot(250, heatmaps) > snapshot(20, heatmaps) + 10_000);
+}
+
+#[test]
+fn check_species_are_sent_when_they_change() {
+    let mut world = World::new(get_config(), 0);
+    world.grid.species_of.insert(1, 7);
+    let parts = SnapshotParts::default();
+    let (mut gui, cells) = world.snapshot(parts);
+    gui.apply_update(None, cells);
+    assert_eq!(gui.species_of.len(), 1);
+
+    // the GUI keeps the species it has until the epoch changes
+    let (mut next, cells) = world.snapshot(parts);
+    assert!(next.species_of.is_empty());
+    next.apply_update(Some(gui), cells);
+    assert_eq!(next.species_of.len(), 1);
+
+    world.grid.species_of.clear();
+    world.grid.species_epoch += 1;
+    let (mut last, cells) = world.snapshot(parts);
+    last.apply_update(Some(next), cells);
+    assert!(last.species_of.is_empty());
+}
+
+#[test]
+fn check_until_conditions() {
+    let config = Settings {
+        creatures: vec![
+            ("noop".to_string(), 5),
+            ("scavenger".to_string(), 5),
+            ("random".to_string(), 0),
+        ],
+        grass_rate: 50,
+        ..get_config()
+    };
+    let mut world = World::new(config, 0);
+    world.populate();
+
+    let until = |text: &str| text.parse::<Until>().unwrap();
+    assert_eq!(until(" tick=100"), Until::Tick(100));
+    assert_eq!(until("population<10").to_string(), "population<10");
+    assert!("extinct=".parse::<Until>().is_err());
+    assert!("ticks>5".parse::<Until>().is_err());
+
+    // creatures placed on the same cell are skipped so count them
+    let count = world.creature_count();
+    assert!(!until(&format!("population<{}", count)).met(&world));
+    assert!(until(&format!("population<{}", count + 1)).met(&world));
+    assert!(!until("extinct=noop").met(&world));
+    // scavengers are random genotypes but are named for their diet
+    assert!(!until("extinct=scavenger").met(&world));
+    assert!(until("extinct=random").met(&world));
+    assert_eq!(world.genotype_counts()["random"], 0);
+    assert!(world.genotype_counts()["scavenger"] > 0);
+
+    // only the genotypes in the settings can go extinct
+    assert!(until("extinct=random").check(world.config()).is_ok());
+    assert!(until("extinct=giles").check(world.config()).is_err());
+    assert!(!until("tick=1").met(&world));
+    world.tick();
+    assert!(until("tick=1").met(&world));
+}
+
+#[test]
+fn check_checkpoints_rotate() {
+    let dir = std::env::temp_dir().join(format!("eyes2-checkpoints-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let config = Settings {
+        autosave_ticks: 10,
+        autosave_keep: 2,
+        grass_rate: 50,
+        ..get_config()
+    };
+    let mut world = World::new(config, 0);
+    let mut autosave = store::Autosave::new(&world);
+
+    let mut saved = Vec::new();
+    while saved.len() < 3 {
+        world.tick();
+        if autosave.due(&world) {
+            saved.push(autosave.save(&world, &dir).unwrap());
+        }
+    }
+    assert_eq!(world.grid.ticks, 30);
+    // the third checkpoint replaces the first
+    assert_eq!(saved[0], saved[2]);
+    assert_ne!(saved[0], saved[1]);
+
+    // a damaged checkpoint is passed over for the one before
+    let (loaded, path) = store::load_checkpoint(&dir).unwrap();
+    assert_eq!((loaded.grid.ticks, &path), (30, &saved[2]));
+    std::fs::write(&saved[2], "grid: [").unwrap();
+    let (loaded, path) = store::load_checkpoint(&dir).unwrap();
+    assert_eq!((loaded.grid.ticks, &path), (20, &saved[1]));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn check_restarts_carry_the_best_genomes() {
+    let config = Settings {
+        creatures: vec![("noop".to_string(), 6)],
+        creature_initial_energy: (2, 10),
+        restart_policy: RestartPolicy::Reseed,
+        restart_keep: 2,
+        grass_count: 0,
+        grass_rate: 50,
+        ..get_config()
+    };
+    let mut world = World::new(config.clone(), 0);
+    world.populate();
+    while world.creature_count() > 0 {
+        world.tick();
+    }
+    // the two that lived longest are kept
+    let seeds = world.seeds().to_vec();
+    assert_eq!(seeds.len(), 2);
+    assert!(seeds[0].lifetime >= seeds[1].lifetime);
+
+    let mut next = World::new(config, 1);
+    next.populate_from(seeds);
+    assert!(next.creature_count() > 0 && next.creature_count() <= 6);
+    assert_eq!(next.seeds().len(), 2);
+}
+
+#[test]
+fn check_inject_keeps_the_population_up() {
+    let config = Settings {
+        creatures: vec![("noop".to_string(), 2)],
+        restart_policy: RestartPolicy::Inject,
+        restart_threshold: 8,
+        grass_rate: 50,
+        ..get_config()
+    };
+    let mut world = World::new(config, 0);
+    world.populate();
+    for _ in 0..5 {
+        world.tick();
+    }
+    assert_eq!(world.creature_count(), 8);
+    assert_eq!(world.genotype_counts()["noop"], 8);
 }
@@ -0,0 +1,108 @@
+//! Live charts of the population statistics.
+//!
+//! The GUI keeps the recent stats samples that arrive with the world grid
+//! and draws each series as a sparkline: the population of every genotype,
+//! the amount of grass and the mean creature energy.
+//!
+use eyes2_lib::world::StatsSample;
+use std::collections::{BTreeSet, VecDeque};
+
+// the number of stats samples kept for the charts
+pub const CHART_HISTORY: usize = 240;
+// the characters of a sparkline from lowest to highest
+const LEVELS: &[char] = &[' ', '.', ':', '-', '=', '+', '*', '#', '%', '@'];
+
+#[derive(Default)]
+pub struct Charts {
+    samples: VecDeque<StatsSample>,
+}
+
+impl Charts {
+    // record a sample, ignoring repeats of the last one
+    pub fn add(&mut self, sample: &StatsSample) {
+        if let Some(last) = self.samples.back() {
+            if last.ticks == sample.ticks {
+                return;
+            }
+            // the world was reset or loaded so start again
+            if last.ticks > sample.ticks {
+                self.samples.clear();
+            }
+        }
+        self.samples.push_back(sample.clone());
+        while self.samples.len() > CHART_HISTORY {
+            self.samples.pop_front();
+        }
+    }
+
+    // the range of ticks covered by the samples
+    pub fn ticks(&self) -> Option<(u64, u64)> {
+        Some((self.samples.front()?.ticks, self.samples.back()?.ticks))
+    }
+
+    // the line above the charts, the samples only arrive every interval
+    // ticks and never when the interval is 0
+    pub fn heading(&self, interval: u64) -> String {
+        match (self.ticks(), interval) {
+            (_, 0) => "stats are off, set stats_interval to chart them".to_string(),
+            (Some((first, last)), _) => {
+                format!("ticks {} to {}, sampled every {}", first, last, interval)
+            }
+            (None, _) => format!(
+                "waiting for the first stats sample, one every {} ticks",
+                interval
+            ),
+        }
+    }
+
+    // the series to chart as (label, values) with the oldest value first
+    pub fn series(&self) -> Vec<(String, Vec<f32>)> {
+        let mut series = vec![(
+            "population".to_string(),
+            self.values(|s| s.creatures as f32),
+        )];
+
+        // a genotype that died out is charted as zero after it went
+        let names: BTreeSet<&str> = self
+            .samples
+            .iter()
+            .flat_map(|s| s.genotypes.iter().map(|g| g.name.as_str()))
+            .collect();
+        for name in names {
+            let values = self.values(|s| {
+                s.genotypes
+                    .iter()
+                    .find(|g| g.name == name)
+                    .map_or(0.0, |g| g.count as f32)
+            });
+            series.push((name.to_string(), values));
+        }
+
+        series.push(("grass".to_string(), self.values(|s| s.grass as f32)));
+        series.push(("mean energy".to_string(), self.values(|s| s.mean_energy)));
+        series
+    }
+
+    fn values(&self, value: impl Fn(&StatsSample) -> f32) -> Vec<f32> {
+        self.samples.iter().map(value).collect()
+    }
+}
+
+// the last width values as a line of characters scaled from zero to the
+// largest value, anything above zero is at least the second level
+pub fn sparkline(values: &[f32], width: usize) -> String {
+    let shown = &values[values.len().saturating_sub(width)..];
+    let max = shown.iter().cloned().fold(0.0, f32::max);
+    let top = LEVELS.len() - 1;
+    shown
+        .iter()
+        .map(|value| match *value > 0.0 && max > 0.0 {
+            true => LEVELS[((value / max * top as f32).round() as usize).clamp(1, top)],
+            false => LEVELS[0],
+        })
+        .collect()
+}
+
+#[path = "charts_test.rs"]
+#[cfg(test)]
+mod test;
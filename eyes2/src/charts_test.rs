@@ -0,0 +1,63 @@
+use super::*;
+use eyes2_lib::world::GenotypeStats;
+
+fn sample(ticks: u64, giles: usize) -> StatsSample {
+    StatsSample {
+        ticks,
+        creatures: giles as u64,
+        grass: 10,
+        species: 1,
+        mean_energy: 50.0,
+        genotypes: vec![GenotypeStats {
+            name: "giles".to_string(),
+            count: giles,
+            distinct: 1,
+            entropy: 0.0,
+            mean_distance: 0.0,
+        }],
+    }
+}
+
+#[test]
+fn test_sparkline() {
+    assert_eq!(sparkline(&[0.0, 1.0, 9.0], 10), " .@");
+    // only the last width values are drawn
+    assert_eq!(sparkline(&[9.0, 0.0, 3.0, 6.0], 3), " +@");
+    assert_eq!(sparkline(&[0.0, 0.0], 5), "  ");
+}
+
+#[test]
+fn test_charts_history() {
+    let mut charts = Charts::default();
+    charts.add(&sample(100, 5));
+    charts.add(&sample(100, 5));
+    charts.add(&sample(200, 0));
+    assert_eq!(charts.ticks(), Some((100, 200)));
+
+    let series = charts.series();
+    let names: Vec<&str> = series.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["population", "giles", "grass", "mean energy"]);
+    assert_eq!(series[1].1, [5.0, 0.0]);
+
+    // a reset world starts the charts again
+    charts.add(&sample(50, 3));
+    assert_eq!(charts.ticks(), Some((50, 50)));
+}
+
+#[test]
+fn test_charts_heading() {
+    let mut charts = Charts::default();
+    assert_eq!(
+        charts.heading(0),
+        "stats are off, set stats_interval to chart them"
+    );
+    assert_eq!(
+        charts.heading(1000),
+        "waiting for the first stats sample, one every 1000 ticks"
+    );
+    charts.add(&sample(1000, 5));
+    assert_eq!(
+        charts.heading(1000),
+        "ticks 1000 to 1000, sampled every 1000"
+    );
+}
@@ -0,0 +1,141 @@
+//! A step debugger for the genotype of a single creature.
+//!
+//! The debugger runs alongside the world loop. It traces the selected
+//! creature so that its last vision and action are recorded and checks
+//! its breakpoints after every tick, pausing the world when one is hit.
+//!
+use eyes2_lib::entity::CreatureDebug;
+use eyes2_lib::World;
+use std::collections::BTreeSet;
+
+// what the GUI shows of the debugger
+#[derive(Clone, Debug)]
+pub struct DebugView {
+    // the id of the selected creature
+    pub id: u64,
+    // the creature's state, None once it has died
+    pub creature: Option<CreatureDebug>,
+    pub breakpoints: Vec<usize>,
+    pub action_breakpoints: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct Debugger {
+    active: bool,
+    // the creature being debugged
+    selected: Option<u64>,
+    // instruction addresses that pause the world before they execute
+    breakpoints: BTreeSet<usize>,
+    // kinds of action that pause the world after they are chosen
+    action_breakpoints: BTreeSet<String>,
+}
+
+impl Debugger {
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    // turn debugging on (selecting the first creature) or off
+    pub fn toggle(&mut self, world: &mut World) {
+        self.active = !self.active;
+        match self.active {
+            true => self.select(world, first_id(world, 0)),
+            false => self.select(world, None),
+        }
+    }
+
+    // select the creature with the next highest id
+    pub fn next_creature(&mut self, world: &mut World) {
+        let after = self.selected.unwrap_or(0) + 1;
+        let next = first_id(world, after).or_else(|| first_id(world, 0));
+        self.select(world, next);
+    }
+
+    // turn debugging on if need be and select a creature
+    pub fn inspect(&mut self, world: &mut World, id: u64) {
+        self.active = true;
+        self.select(world, Some(id));
+    }
+
+    pub fn select(&mut self, world: &mut World, id: Option<u64>) {
+        if let Some(old) = self.selected {
+            world.set_traced(old, false);
+        }
+        if let Some(new) = id {
+            world.set_traced(new, true);
+        }
+        self.selected = id;
+    }
+
+    // trace the selected creature again in a new or reloaded world
+    pub fn retrace(&mut self, world: &mut World) {
+        if let Some(id) = self.selected {
+            world.set_traced(id, true);
+        }
+    }
+
+    pub fn toggle_breakpoint(&mut self, address: usize) {
+        if !self.breakpoints.remove(&address) {
+            self.breakpoints.insert(address);
+        }
+    }
+
+    // toggle a breakpoint on the kind of action the creature last chose
+    pub fn toggle_action_breakpoint(&mut self, world: &World) {
+        let Some(creature) = self.creature(world) else {
+            return;
+        };
+        if creature.action_kind.is_empty() {
+            return;
+        }
+        if !self.action_breakpoints.remove(&creature.action_kind) {
+            self.action_breakpoints.insert(creature.action_kind);
+        }
+    }
+
+    // true if the selected creature has reached a breakpoint, this runs on
+    // every tick so it avoids building the creature's full debug view
+    pub fn hit(&self, world: &World) -> bool {
+        if !self.active || (self.breakpoints.is_empty() && self.action_breakpoints.is_empty()) {
+            return false;
+        }
+        match self.selected.and_then(|id| world.creature(id)) {
+            Some(creature) => {
+                creature
+                    .ip()
+                    .is_some_and(|ip| self.breakpoints.contains(&ip))
+                    || self.action_breakpoints.contains(creature.action_kind())
+            }
+            None => false,
+        }
+    }
+
+    pub fn view(&self, world: &World) -> Option<DebugView> {
+        if !self.active {
+            return None;
+        }
+        Some(DebugView {
+            id: self.selected.unwrap_or(0),
+            creature: self.creature(world),
+            breakpoints: self.breakpoints.iter().cloned().collect(),
+            action_breakpoints: self.action_breakpoints.iter().cloned().collect(),
+        })
+    }
+
+    fn creature(&self, world: &World) -> Option<CreatureDebug> {
+        world.creature(self.selected?).map(|c| c.debug())
+    }
+}
+
+// the lowest creature id at or above the given id
+fn first_id(world: &World, from: u64) -> Option<u64> {
+    world
+        .creatures()
+        .map(|c| c.id())
+        .filter(|id| *id >= from)
+        .min()
+}
+
+#[path = "debugger_test.rs"]
+#[cfg(test)]
+mod test;
@@ -0,0 +1,82 @@
+use super::*;
+use eyes2_lib::entity::{Creature, GilesGenotype, Instruction};
+use eyes2_lib::Settings;
+
+// a world holding one giles creature that runs nop, look in a loop
+fn get_world() -> World {
+    let config = Settings {
+        size: 10,
+        grass_count: 0,
+        grass_rate: 50,
+        creatures: Vec::new(),
+        ..Settings::default()
+    };
+    let mut world = World::new(config.clone(), 0);
+    let genome = vec![Instruction::Nop, Instruction::Look];
+    let genotype = GilesGenotype::from_genome(config.clone(), genome);
+    world.introduce(Creature::detached(Box::new(genotype), config));
+    world
+}
+
+#[test]
+fn test_breakpoints() {
+    let mut world = get_world();
+    let mut debugger = Debugger::default();
+    debugger.toggle_breakpoint(1);
+    // breakpoints only pause the world while debugging
+    assert!(!debugger.hit(&world));
+
+    debugger.toggle(&mut world);
+    assert!(debugger.is_active());
+    assert!(!debugger.hit(&world));
+    world.tick();
+    assert!(debugger.hit(&world));
+
+    debugger.toggle_breakpoint(1);
+    assert!(!debugger.hit(&world));
+    assert_eq!(
+        debugger.view(&world).unwrap().breakpoints,
+        Vec::<usize>::new()
+    );
+
+    debugger.toggle(&mut world);
+    assert!(debugger.view(&world).is_none());
+}
+
+#[test]
+fn test_action_breakpoints() {
+    let mut world = get_world();
+    let mut debugger = Debugger::default();
+    debugger.toggle(&mut world);
+    world.tick();
+    world.tick();
+    assert_eq!(
+        debugger.view(&world).unwrap().creature.unwrap().action_kind,
+        "look"
+    );
+
+    debugger.toggle_action_breakpoint(&world);
+    assert_eq!(debugger.view(&world).unwrap().action_breakpoints, ["look"]);
+    assert!(debugger.hit(&world));
+    debugger.toggle_action_breakpoint(&world);
+    assert!(!debugger.hit(&world));
+}
+
+#[test]
+fn test_retrace() {
+    let mut world = get_world();
+    let mut debugger = Debugger::default();
+    debugger.toggle(&mut world);
+
+    // a reloaded world has no traced creatures until the debugger retraces
+    let yaml = serde_yaml::to_string(&world).unwrap();
+    let mut world: World = serde_yaml::from_str(&yaml).unwrap();
+    world.tick();
+    let creature = debugger.view(&world).unwrap().creature.unwrap();
+    assert_eq!(creature.action_kind, "");
+
+    debugger.retrace(&mut world);
+    world.tick();
+    let creature = debugger.view(&world).unwrap().creature.unwrap();
+    assert_ne!(creature.action_kind, "");
+}
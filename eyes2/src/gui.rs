@@ -1,10 +1,20 @@
 //! The GUI for the evolution simulation. Renders the current state of the world
 //! and handles user input.
 //!
+use crate::charts::{sparkline, Charts};
+use crate::debugger::DebugView;
+use crate::keymap::{Action, Keymap};
+use crate::settings_editor::{EditorAction, SettingsEditor};
+use crate::theme::{self, Palette, Rgb, Theme};
+use crate::viewport::{line, Viewport};
 use chrono::Utc;
-use eyes2_lib::{Cell, WorldGrid};
+use eyes2_lib::entity::Vision;
+use eyes2_lib::world::CellUpdate;
+use eyes2_lib::world::{Brush, Heatmap, SnapshotParts, Until};
+use eyes2_lib::{Cell, Settings, WorldGrid};
 
 use num_format::{Locale, ToFormattedString};
+use std::collections::HashMap;
 use std::error::Error;
 use std::sync::mpsc;
 use std::thread;
@@ -15,8 +25,10 @@ use std::{
 
 use direction::Coord;
 use pancurses::{
-    endwin, init_pair, initscr, start_color, ColorPair, COLOR_BLACK, COLOR_BLUE, COLOR_GREEN,
-    COLOR_RED, COLOR_WHITE,
+    endwin, init_pair, initscr, start_color, ColorPair, Input, ALL_MOUSE_EVENTS, A_REVERSE,
+    BUTTON1_PRESSED, BUTTON1_RELEASED, BUTTON4_PRESSED, BUTTON5_PRESSED, BUTTON_SHIFT, COLOR_BLACK,
+    COLOR_BLUE, COLOR_CYAN, COLOR_GREEN, COLOR_MAGENTA, COLOR_RED, COLOR_WHITE, COLOR_YELLOW,
+    REPORT_MOUSE_POSITION,
 };
 
 const RED: u8 = 1;
@@ -25,6 +37,31 @@ const BLACK: u8 = 3;
 const BLUE: u8 = 4;
 const CREATURE: u8 = 5;
 const WALL: u8 = 6;
+// each species of creature is drawn with one of these colours
+const SPECIES: u8 = 10;
+const SPECIES_COLOURS: [i16; 6] = [
+    COLOR_YELLOW,
+    COLOR_CYAN,
+    COLOR_MAGENTA,
+    COLOR_RED,
+    COLOR_GREEN,
+    COLOR_BLUE,
+];
+// the background colours of a heatmap overlay from cold to hot
+const HEAT: u8 = 20;
+const HEAT_COLOURS: [i16; 5] = [
+    COLOR_BLACK,
+    COLOR_BLUE,
+    COLOR_GREEN,
+    COLOR_YELLOW,
+    COLOR_RED,
+];
+// the colour pairs of the unicode themes come after the fixed ones
+const PALETTE: i16 = 32;
+// the number of species listed in the status pane
+const TOP_SPECIES: usize = 3;
+// the number of world cells a shifted scroll wheel pans by
+const PAN_STEP: i32 = 4;
 
 #[derive(Debug, Clone)]
 pub enum GuiCmd {
@@ -40,6 +77,60 @@ pub enum GuiCmd {
     SpeedMax,
     GrassUp,
     GrassDown,
+    // debugger commands
+    Debug,
+    NextCreature,
+    Step,
+    Breakpoint(usize),
+    ActionBreakpoint,
+    // inspect the creature in a cell with the debugger
+    Select(Coord),
+    // paint cells of the world in edit mode
+    Paint(Vec<Coord>, Brush),
+    // settings editor commands
+    EditSettings,
+    // the edited settings and true to save them as the default config
+    ApplySettings(Box<Settings>, bool),
+    // run until a condition is met then pause
+    RunUntil(Until),
+    // run a number of ticks as fast as possible then pause
+    FastForward(u64),
+    // the parts of the grid the GUI now draws (see WorldGrid::snapshot)
+    Show(SnapshotParts),
+}
+
+// the state of the world sent to the GUI for each frame
+pub struct Frame {
+    // the grid without its cells (see WorldGrid::snapshot)
+    pub grid: WorldGrid,
+    pub cells: CellUpdate,
+    // the debugger state when the debugger is active
+    pub debug: Option<DebugView>,
+    // the settings to edit in reply to GuiCmd::EditSettings
+    pub settings: Option<Settings>,
+    // whether the world is paused or running and until when
+    pub control: String,
+    // how often the world records a stats sample, 0 for never
+    pub stats_interval: u64,
+}
+
+// a value typed in the status pane for a command that needs one
+struct Prompt {
+    // RunUntil or FastForward
+    action: Action,
+    text: String,
+    // why the last value typed was not accepted
+    error: Option<String>,
+}
+
+// what changes the look of every cell in a frame
+struct Look {
+    // the creature being debugged
+    selected: Option<Coord>,
+    // the heatmap overlay and its highest value
+    overlay: Option<(Heatmap, f32)>,
+    // the energy of the most energetic creature
+    max_energy: i32,
 }
 
 pub struct EyesGui {
@@ -51,9 +142,44 @@ pub struct EyesGui {
     x_max: i32,
     last_tick: u64,
     last_tick_time: Instant,
+    // true if the last frame had the debugger active
+    debugging: bool,
+    // the address in the debugger listing to toggle breakpoints on
+    cursor: usize,
+    // the heatmap drawn over the world, if any
+    overlay: Option<Heatmap>,
+    // true to show the population charts in place of the world
+    show_charts: bool,
+    charts: Charts,
+    // the stats_interval of the last frame
+    stats_interval: u64,
+    // the settings editor and its window while it is open
+    editor: Option<(SettingsEditor, pancurses::Window)>,
+    // the part of the world shown in the left pane
+    viewport: Viewport,
+    // the brush used by the mouse in edit mode, None outside edit mode
+    brush: Option<Brush>,
+    // the last cell painted while the mouse button is held down
+    drag: Option<Coord>,
+    // the size of the world in the last frame
+    grid_size: i32,
+    // the world as of the last frame, updated with the changed cells
+    grid: Option<WorldGrid>,
+    // what was drawn last frame that changes the look of unchanged cells
+    selected: Option<Coord>,
+    species_epoch: u64,
+    // how the world is drawn and the colour pairs it has used
+    theme: Theme,
+    palette: Palette,
+    // the energy band of each creature when it was last drawn
+    bands: HashMap<u64, i32>,
+    // true to draw every cell in the next frame
+    redraw: bool,
+    keymap: Keymap,
+    prompt: Option<Prompt>,
 }
 
-const DATE_FMT: &'static str = "%y-%m-%d %H:%M:%S";
+const DATE_FMT: &str = "%y-%m-%d %H:%M:%S";
 
 // tying out a macro here! Just a simple incrementor to save a few lines of code
 macro_rules! inc {
@@ -64,12 +190,12 @@ macro_rules! inc {
 }
 
 impl EyesGui {
-    pub fn new() -> EyesGui {
+    pub fn new(keymap: Keymap) -> EyesGui {
         let window = initscr();
         // choose some minimal initial sizes
         let left_pane = pancurses::newwin(1, 1, 0, 0);
         let right_pane = pancurses::newwin(1, 1, 0, 3);
-        let help_pane = pancurses::newwin(20, 44, 3, 10);
+        let help_pane = pancurses::newwin(1, 1, 0, 0);
 
         start_color();
         init_pair(RED as i16, COLOR_RED, COLOR_BLACK);
@@ -78,6 +204,12 @@ impl EyesGui {
         init_pair(GREEN as i16, COLOR_GREEN, COLOR_BLACK);
         init_pair(BLUE as i16, COLOR_BLUE, COLOR_BLACK);
         init_pair(BLACK as i16, COLOR_BLACK, COLOR_BLACK);
+        for (i, colour) in SPECIES_COLOURS.iter().enumerate() {
+            init_pair(SPECIES as i16 + i as i16, *colour, COLOR_BLACK);
+        }
+        for (i, colour) in HEAT_COLOURS.iter().enumerate() {
+            init_pair(HEAT as i16 + i as i16, COLOR_WHITE, *colour);
+        }
 
         window.timeout(0);
         window.keypad(true);
@@ -88,6 +220,10 @@ impl EyesGui {
         pancurses::curs_set(0);
         pancurses::noecho();
 
+        // report presses and releases separately so that drags can be seen
+        pancurses::mousemask(ALL_MOUSE_EVENTS | REPORT_MOUSE_POSITION, None);
+        pancurses::mouseinterval(0);
+
         EyesGui {
             window,
             left_pane,
@@ -97,12 +233,32 @@ impl EyesGui {
             x_max: 0,
             last_tick: 0,
             last_tick_time: time::Instant::now(),
+            debugging: false,
+            cursor: 0,
+            overlay: None,
+            show_charts: false,
+            charts: Charts::default(),
+            stats_interval: 0,
+            editor: None,
+            viewport: Viewport::default(),
+            brush: None,
+            drag: None,
+            grid_size: 0,
+            grid: None,
+            selected: None,
+            species_epoch: 0,
+            theme: Theme::Classic,
+            palette: Palette::new(PALETTE),
+            bands: HashMap::new(),
+            redraw: false,
+            keymap,
+            prompt: None,
         }
     }
 
     pub fn gui_loop(
         &mut self,
-        rx_grid: mpsc::Receiver<WorldGrid>,
+        rx_grid: mpsc::Receiver<Frame>,
         tx_gui_cmd: mpsc::Sender<GuiCmd>,
     ) -> Result<(), Box<dyn Error>> {
         loop {
@@ -114,24 +270,103 @@ impl EyesGui {
                 _ => {}
             }
 
-            let grid: WorldGrid = rx_grid.recv()?;
-            self.render(grid);
+            let frame: Frame = rx_grid.recv()?;
+            self.render(frame);
             thread::sleep(time::Duration::from_millis(50));
         }
         Ok(())
     }
 
-    pub fn render(&mut self, grid: WorldGrid) {
-        let grid_ref = &grid;
+    pub fn render(&mut self, frame: Frame) {
+        let Frame {
+            mut grid,
+            cells,
+            debug,
+            settings,
+            control,
+            stats_interval,
+        } = frame;
+        self.stats_interval = stats_interval;
+        // only the changed cells need drawing unless the world is new
+        let changed = match &cells {
+            CellUpdate::Full(_) => None,
+            CellUpdate::Changed(changes) => Some(changes.iter().map(|(i, _)| *i).collect()),
+        };
+        grid.apply_update(self.grid.take(), cells);
+        self.draw(&grid, changed, debug, settings, &control);
+        self.grid = Some(grid);
+    }
+
+    fn draw(
+        &mut self,
+        grid: &WorldGrid,
+        mut changed: Option<Vec<usize>>,
+        debug: Option<DebugView>,
+        settings: Option<Settings>,
+        control: &str,
+    ) {
         let (y_max, x_max) = self.window.get_max_yx();
+        if grid.get_size() as i32 != self.grid_size {
+            self.grid_size = grid.get_size() as i32;
+            self.y_max = 0;
+        }
 
+        // the editor covers the whole screen while it is open
+        if let Some(settings) = settings {
+            let window = pancurses::newwin(y_max, x_max, 0, 0);
+            window.keypad(true);
+            window.nodelay(true);
+            self.editor = Some((SettingsEditor::new(settings, &self.keymap), window));
+        }
+        if self.editor.is_some() {
+            self.render_editor();
+            return;
+        }
+
+        // the debugger replaces the status pane so redraw when it toggles
+        if debug.is_some() != self.debugging {
+            self.debugging = debug.is_some();
+            self.y_max = 0;
+        }
         if (y_max, x_max) != (self.y_max, self.x_max) {
             (self.y_max, self.x_max) = (y_max, x_max);
             self.right_pane.clear();
-            self.resize(grid_ref);
+            self.resize(grid);
             self.window.refresh();
+            changed = None;
+        }
+        let selected = debug
+            .as_ref()
+            .and_then(|d| d.creature.as_ref())
+            .map(|c| c.coord);
+        // these change the look of cells that have not changed
+        if self.overlay.is_some()
+            || selected != self.selected
+            || grid.species_epoch != self.species_epoch
+            || self.redraw
+        {
+            changed = None;
+        }
+        self.redraw = false;
+        (self.selected, self.species_epoch) = (selected, grid.species_epoch);
+        // as does a new energy band for a creature
+        let energy_changes = self.energy_changes(grid);
+        if let Some(changed) = changed.as_mut() {
+            changed.extend(energy_changes);
+        }
+
+        if let Some(sample) = &grid.latest_stats {
+            self.charts.add(sample);
+        }
+        match self.show_charts {
+            true => self.render_charts(),
+            false => self.render_grid(grid, selected, changed.as_deref()),
+        }
+
+        if let Some(debug) = debug {
+            self.render_debug(grid, &debug);
+            return;
         }
-        self.render_grid(grid_ref);
 
         let l = &Locale::en;
         let rate = {
@@ -159,26 +394,116 @@ impl EyesGui {
         inc!(y);
         self.status(inc!(y), "speed:", &grid.speed.to_string());
         self.status(inc!(y), "grass rate:", &grid.grass_rate.to_string());
+        self.status(inc!(y), "season:", &format!("{:.0}%", grid.season * 100.0));
+        let overlay = self.overlay.map_or("none", |map| map.name());
+        self.status(inc!(y), "overlay:", overlay);
+        let brush = self.brush.map_or("off", |brush| brush.name());
+        self.status(inc!(y), "paint:", brush);
+        self.status(inc!(y), "zoom:", &format!("{}x", self.viewport.zoom));
+        self.status(inc!(y), "theme:", self.theme.name());
+        inc!(y);
+        self.status(inc!(y), "species:", &grid.species.len().to_string());
+        for i in 0..TOP_SPECIES {
+            let text = match grid.species.get(i) {
+                Some(s) => format!("#{} '{}' {}", s.id, s.sigil, s.size),
+                None => String::new(),
+            };
+            let label = if i == 0 { "largest:" } else { "" };
+            self.status(inc!(y), label, &text);
+        }
+        inc!(y);
+        self.status(inc!(y), "run:", control);
+        self.render_prompt(inc!(y));
 
-        self.footer(" q: quit, h: help ");
+        let footer = self
+            .keymap
+            .footer(&[(Action::Quit, "quit"), (Action::Help, "help")]);
+        self.footer(&footer);
     }
 
     pub fn get_cmd(&mut self) -> GuiCmd {
-        let result = match self.window.getch() {
-            Some(pancurses::Input::Character('q')) => GuiCmd::Quit,
-            Some(pancurses::Input::Character(' ')) => GuiCmd::Pause,
-            Some(pancurses::Input::Character('r')) => GuiCmd::Reset,
-            Some(pancurses::Input::Character('s')) => GuiCmd::Save,
-            Some(pancurses::Input::Character('l')) => GuiCmd::Load,
-            Some(pancurses::Input::KeyUp) => GuiCmd::SpeedUp,
-            Some(pancurses::Input::KeyDown) => GuiCmd::SpeedDown,
-            Some(pancurses::Input::KeyRight) => GuiCmd::GrassUp,
-            Some(pancurses::Input::KeyLeft) => GuiCmd::GrassDown,
-            Some(pancurses::Input::Character('h')) => {
+        if self.editor.is_some() {
+            return self.editor_cmd();
+        }
+        if self.prompt.is_some() {
+            return self.prompt_cmd();
+        }
+        let action = match self.window.getch() {
+            Some(pancurses::Input::KeyMouse) => {
+                let cmd = self.mouse_cmd();
+                pancurses::flushinp();
+                return cmd;
+            }
+            Some(key) => self.keymap.action(key, self.debugging),
+            None => None,
+        };
+        let result = match action {
+            Some(Action::Quit) => GuiCmd::Quit,
+            Some(Action::Pause) => GuiCmd::Pause,
+            Some(Action::Reset) => GuiCmd::Reset,
+            Some(Action::Save) => GuiCmd::Save,
+            Some(Action::Load) => GuiCmd::Load,
+            Some(Action::SpeedUp) => GuiCmd::SpeedUp,
+            Some(Action::SpeedDown) => GuiCmd::SpeedDown,
+            Some(Action::GrassUp) => GuiCmd::GrassUp,
+            Some(Action::GrassDown) => GuiCmd::GrassDown,
+            Some(Action::Overlay) => {
+                self.next_overlay();
+                GuiCmd::Show(self.parts())
+            }
+            Some(Action::Charts) => {
+                self.show_charts = !self.show_charts;
+                self.left_pane.clear();
+                // force a resize which will redraw everything
+                self.y_max = 0;
+                GuiCmd::None
+            }
+            Some(Action::Brush) => {
+                self.next_brush();
+                GuiCmd::None
+            }
+            Some(Action::Theme) => {
+                self.theme = self.theme.next();
+                self.viewport.rows = self.theme.rows();
+                self.left_pane.clear();
+                self.y_max = 0;
+                GuiCmd::Show(self.parts())
+            }
+            Some(Action::ResetZoom) => {
+                self.viewport = Viewport {
+                    rows: self.theme.rows(),
+                    ..Viewport::default()
+                };
+                self.y_max = 0;
+                GuiCmd::None
+            }
+            Some(Action::EditSettings) => GuiCmd::EditSettings,
+            Some(Action::Debug) => GuiCmd::Debug,
+            Some(Action::NextCreature) => GuiCmd::NextCreature,
+            Some(Action::Step) => GuiCmd::Step,
+            Some(action @ (Action::RunUntil | Action::FastForward)) => {
+                self.prompt = Some(Prompt {
+                    action,
+                    text: String::new(),
+                    error: None,
+                });
+                GuiCmd::None
+            }
+            Some(Action::Breakpoint) => GuiCmd::Breakpoint(self.cursor),
+            Some(Action::ActionBreakpoint) => GuiCmd::ActionBreakpoint,
+            Some(Action::CursorUp) => {
+                self.cursor = self.cursor.saturating_sub(1);
+                GuiCmd::None
+            }
+            Some(Action::CursorDown) => {
+                self.cursor += 1;
+                GuiCmd::None
+            }
+            Some(Action::Help) => {
                 self.show_help();
                 GuiCmd::None
             }
-            _ => GuiCmd::None,
+            None => GuiCmd::None,
         };
         pancurses::flushinp();
 
@@ -190,11 +515,11 @@ impl EyesGui {
 impl EyesGui {
     fn resize(&mut self, grid: &WorldGrid) -> bool {
         let status_width = 33;
-        let world_width = grid.get_size();
+        let world = self.viewport.pane_size(grid.get_size() as i32);
 
         let mut x_space = self.x_max - status_width;
-        x_space = x_space.clamp(0, world_width as i32);
-        let y_space = self.y_max.clamp(0, world_width as i32);
+        x_space = x_space.clamp(0, world.x);
+        let y_space = self.y_max.clamp(0, world.y);
         let w_stats = max(min(status_width, 1 + self.x_max - x_space), 0);
 
         self.left_pane.resize(y_space, x_space);
@@ -202,41 +527,533 @@ impl EyesGui {
         self.right_pane.resize(y_space, w_stats);
 
         self.right_pane.draw_box(0, 0);
+        // a bigger pane may show more of the world than before
+        let pane = Coord {
+            x: x_space,
+            y: y_space,
+        };
+        self.viewport
+            .pan(Coord { x: 0, y: 0 }, pane, grid.get_size() as i32);
         x_space > 0 && self.y_max > 0
     }
 
-    fn render_grid(&mut self, grid: &WorldGrid) {
+    // draw the cells at the given indices, or every cell if there are none
+    fn render_grid(
+        &mut self,
+        grid: &WorldGrid,
+        selected: Option<Coord>,
+        changed: Option<&[usize]>,
+    ) {
         let (height, width) = self.left_pane.get_max_yx();
-        for y in 0..height {
-            self.left_pane.mv(y, 0);
-            for x in 0..width {
-                match grid.get_cell(Coord { x, y }) {
-                    Cell::Empty => {
-                        self.left_pane.attron(ColorPair(BLACK));
-                        self.left_pane.addstr(" ");
-                        self.left_pane.attroff(ColorPair(BLACK));
-                    }
-                    Cell::Grass => {
-                        self.left_pane.attron(ColorPair(GREEN));
-                        self.left_pane.printw("o");
-                        self.left_pane.attroff(ColorPair(GREEN));
-                    }
-                    Cell::Entity(_, sigil) => {
-                        self.left_pane.attron(ColorPair(CREATURE));
-                        self.left_pane.addch(sigil);
-                        self.left_pane.attroff(ColorPair(CREATURE));
+        let overlay = self.overlay.map(|map| (map, grid.heatmaps.max(map)));
+        let max_energy = theme::max_energy(grid);
+        let look = Look {
+            selected,
+            overlay,
+            max_energy,
+        };
+        let Some(changed) = changed else {
+            for y in 0..height {
+                for x in 0..width {
+                    self.draw_cell(grid, Coord { x, y }, &look);
+                }
+            }
+            self.left_pane.refresh();
+            return;
+        };
+
+        // each cell covers a block of zoom by zoom characters, or half
+        // characters when there are two rows in each
+        let (size, zoom) = (grid.get_size() as usize, self.viewport.zoom);
+        let rows = self.viewport.rows;
+        for index in changed {
+            let cell = Coord {
+                x: (index % size) as i32,
+                y: (index / size) as i32,
+            } - self.viewport.origin;
+            for dy in 0..zoom {
+                for dx in 0..zoom {
+                    let pos = Coord {
+                        x: cell.x * zoom + dx,
+                        y: (cell.y * zoom + dy).div_euclid(rows),
+                    };
+                    if pos.x >= 0 && pos.y >= 0 && pos.x < width && pos.y < height {
+                        self.draw_cell(grid, pos, &look);
                     }
-                    Cell::Wall => {
-                        self.left_pane.attron(ColorPair(WALL));
-                        self.left_pane.addch(' ');
-                        self.left_pane.attroff(ColorPair(WALL));
+                }
+            }
+        }
+        self.left_pane.refresh();
+    }
+
+    // the cells of the creatures whose energy band has changed since the
+    // last frame
+    fn energy_changes(&mut self, grid: &WorldGrid) -> Vec<usize> {
+        let max_energy = theme::max_energy(grid);
+        let size = grid.get_size() as i32;
+        let mut changes = Vec::new();
+        let bands = grid
+            .energies
+            .iter()
+            .map(|(id, (coord, energy))| {
+                let band = theme::energy_band(*energy, max_energy);
+                if self.bands.get(id) != Some(&band) {
+                    changes.push((coord.x + coord.y * size) as usize);
+                }
+                (*id, band)
+            })
+            .collect();
+        self.bands = bands;
+        changes
+    }
+
+    // draw the world cell shown at a position in the left pane
+    fn draw_cell(&mut self, grid: &WorldGrid, pos: Coord, look: &Look) {
+        match self.theme {
+            Theme::Classic => self.draw_classic(grid, pos, look),
+            Theme::Unicode => self.draw_unicode(grid, pos, look),
+            Theme::HalfBlock => self.draw_half_block(grid, pos, look),
+        }
+    }
+
+    fn draw_classic(&self, grid: &WorldGrid, pos: Coord, look: &Look) {
+        let (selected, overlay) = (look.selected, look.overlay);
+        let coord = self.viewport.to_world(pos);
+        // the creature being debugged is shown in reverse video
+        let highlight = selected == Some(coord);
+        if highlight {
+            self.left_pane.attron(A_REVERSE);
+        }
+        let (sigil, mut colour) = match grid.get_cell(coord) {
+            Cell::Empty => (' ', BLACK),
+            Cell::Grass(species, _) => (grid.plant_sigil(species), GREEN),
+            Cell::Corpse(_) => ('x', RED),
+            Cell::Entity(id, sigil) => {
+                // creatures born since the last clustering have no species
+                let colour = match grid.species_of.get(&id) {
+                    Some(species) => SPECIES + (*species as usize % SPECIES_COLOURS.len()) as u8,
+                    None => CREATURE,
+                };
+                (sigil, colour)
+            }
+            Cell::Wall => (' ', WALL),
+        };
+        // an overlay replaces the background with the heat of the cell
+        if let Some((map, max)) = overlay {
+            colour = HEAT + heat_level(grid.heatmaps.get(map, coord), max);
+        }
+        self.left_pane.attron(ColorPair(colour));
+        self.left_pane.mvaddch(pos.y, pos.x, sigil);
+        self.left_pane.attroff(ColorPair(colour));
+        if highlight {
+            self.left_pane.attroff(A_REVERSE);
+        }
+    }
+
+    fn draw_unicode(&mut self, grid: &WorldGrid, pos: Coord, look: &Look) {
+        let coord = self.viewport.to_world(pos);
+        let cell = grid.get_cell(coord);
+        let fg = theme::colour(grid, cell, look.max_energy);
+        let bg = match look.overlay {
+            Some((map, max)) => {
+                theme::HEAT[heat_level(grid.heatmaps.get(map, coord), max) as usize]
+            }
+            None => theme::BLACK,
+        };
+        // the creature being debugged is shown in reverse video
+        let (fg, bg) = match look.selected == Some(coord) {
+            true => (bg, fg),
+            false => (fg, bg),
+        };
+        self.put(pos, theme::glyph(cell), fg, bg);
+    }
+
+    // draw the two cells in a character as the colours of the upper and
+    // lower halves of the character
+    fn draw_half_block(&mut self, grid: &WorldGrid, pos: Coord, look: &Look) {
+        let size = grid.get_size() as i32;
+        let half = |row| {
+            let coord = self.viewport.to_world_row(pos, row);
+            if coord.y >= size {
+                return theme::BLACK;
+            }
+            let cell = grid.get_cell(coord);
+            match (cell, look.overlay) {
+                _ if look.selected == Some(coord) => theme::WHITE,
+                // the overlay shows through the empty cells
+                (Cell::Empty, Some((map, max))) => {
+                    theme::HEAT[heat_level(grid.heatmaps.get(map, coord), max) as usize]
+                }
+                _ => theme::colour(grid, cell, look.max_energy),
+            }
+        };
+        let (upper, lower) = (half(0), half(1));
+        self.put(pos, '▀', upper, lower);
+    }
+
+    // draw a glyph in a colour pair from the palette
+    fn put(&mut self, pos: Coord, glyph: char, fg: Rgb, bg: Rgb) {
+        let pair = self.palette.pair(fg, bg).unwrap_or_else(|| {
+            // the palette starts again so draw everything next frame
+            self.redraw = true;
+            0
+        });
+        self.left_pane.color_set(pair);
+        self.left_pane.mvaddstr(pos.y, pos.x, glyph.to_string());
+        self.left_pane.color_set(0);
+    }
+
+    // pass every waiting key to the prompt, the command is sent when the
+    // value typed is accepted
+    fn prompt_cmd(&mut self) -> GuiCmd {
+        while let (Some(prompt), Some(key)) = (self.prompt.as_mut(), self.window.getch()) {
+            match key {
+                Input::Character('\n') | Input::KeyEnter => {
+                    let text = prompt.text.trim();
+                    let cmd = match prompt.action {
+                        Action::FastForward => match text.parse::<u64>() {
+                            Ok(ticks) => Ok(GuiCmd::FastForward(ticks)),
+                            Err(_) => Err(format!("'{}' is not a number", text)),
+                        },
+                        _ => text.parse::<Until>().map(GuiCmd::RunUntil),
+                    };
+                    match cmd {
+                        Ok(cmd) => {
+                            self.prompt = None;
+                            return cmd;
+                        }
+                        Err(error) => prompt.error = Some(error),
                     }
+                }
+                Input::Character('\x1b') => self.prompt = None,
+                Input::KeyBackspace | Input::Character('\x7f') | Input::Character('\x08') => {
+                    prompt.text.pop();
+                }
+                Input::Character(c) if !c.is_control() => prompt.text.push(c),
+                _ => {}
+            }
+        }
+        GuiCmd::None
+    }
+
+    // the prompt and a hint or the last error on the two lines at pos
+    fn render_prompt(&mut self, pos: i32) {
+        let (label, text, hint) = match &self.prompt {
+            None => ("", String::new(), String::new()),
+            Some(prompt) => {
+                let (label, hint) = match prompt.action {
+                    Action::FastForward => ("ticks:", "the number of ticks to run"),
+                    _ => ("until:", "population<N extinct=NAME tick=T"),
                 };
+                let hint = prompt.error.clone().unwrap_or(hint.to_string());
+                (label, format!("{}_", prompt.text), hint)
+            }
+        };
+        let (_, width) = self.right_pane.get_max_yx();
+        let width = max(0, width - 2) as usize;
+        self.status(pos, &format!("{:<12}", label), &text);
+        if pos + 1 < self.right_pane.get_max_y() - 1 {
+            self.right_pane.mv(pos + 1, 1);
+            self.right_pane
+                .addnstr(format!("{:<1$}", hint, width), width);
+            self.right_pane.refresh();
+        }
+    }
+
+    // pass every waiting key to the settings editor, typed values must not
+    // lose keys to flushinp
+    fn editor_cmd(&mut self) -> GuiCmd {
+        let mut cmd = GuiCmd::None;
+        while let Some((editor, window)) = self.editor.as_mut() {
+            let Some(key) = window.getch() else {
+                break;
+            };
+            match editor.key(key, self.keymap.action(key, false)) {
+                EditorAction::None => {}
+                EditorAction::Close => {
+                    self.editor = None;
+                    // force a resize which will redraw everything
+                    self.left_pane.touch();
+                    self.y_max = 0;
+                }
+                EditorAction::Apply(settings) => {
+                    cmd = GuiCmd::ApplySettings(Box::new(settings), false)
+                }
+                EditorAction::Save(settings) => {
+                    cmd = GuiCmd::ApplySettings(Box::new(settings), true)
+                }
+            }
+        }
+        cmd
+    }
+
+    fn render_editor(&mut self) {
+        let Some((editor, window)) = self.editor.as_ref() else {
+            return;
+        };
+        // keep clear of the last column, writing the bottom right corner
+        // scrolls the window
+        let (height, width) = window.get_max_yx();
+        for (y, (text, highlight)) in editor
+            .lines(height as usize, width as usize - 1)
+            .iter()
+            .enumerate()
+        {
+            if *highlight {
+                window.attron(A_REVERSE);
+            }
+            window.mvaddstr(y as i32, 0, text);
+            if *highlight {
+                window.attroff(A_REVERSE);
+            }
+        }
+        window.refresh();
+    }
+
+    // a sparkline for each series with its label and latest value above
+    fn render_charts(&mut self) {
+        let (height, width) = self.left_pane.get_max_yx();
+        let width = width as usize;
+        let heading = self.charts.heading(self.stats_interval);
+        self.left_pane
+            .mvaddnstr(0, 0, format!("{:<1$}", heading, width), width as i32);
+
+        let mut y = 1;
+        for (label, values) in self.charts.series() {
+            if y + 2 >= height {
+                break;
             }
+            let latest = values.last().cloned().unwrap_or(0.0);
+            let peak = values.iter().cloned().fold(0.0, f32::max);
+            let text = format!("{}: {:.0} (max {:.0})", label, latest, peak);
+            self.left_pane.attron(ColorPair(BLUE));
+            self.left_pane
+                .mvaddnstr(inc!(y), 0, format!("{:<1$}", text, width), width as i32);
+            self.left_pane.attroff(ColorPair(BLUE));
+            let line = format!("{:>1$}", sparkline(&values, width), width);
+            self.left_pane.mvaddnstr(inc!(y), 0, line, width as i32);
+        }
+        // clear what is left of the pane, the series can change
+        for y in y + 1..height {
+            self.left_pane.mv(y, 0);
+            self.left_pane.clrtoeol();
         }
         self.left_pane.refresh();
     }
 
+    // show the selected creature's state in place of the status pane
+    fn render_debug(&mut self, grid: &WorldGrid, debug: &DebugView) {
+        let mut y = 0;
+        self.status(inc!(y), "ticks:", &grid.ticks.to_string());
+        let Some(creature) = &debug.creature else {
+            self.status(inc!(y), "creature:", &format!("{} has died", debug.id));
+            let footer = self
+                .keymap
+                .footer(&[(Action::NextCreature, "next creature")]);
+            self.footer(&footer);
+            return;
+        };
+        let at = format!(
+            "{} '{}' at {},{}",
+            creature.id, creature.sigil, creature.coord.x, creature.coord.y
+        );
+        self.status(inc!(y), "creature:", &at);
+        self.status(inc!(y), "energy:", &creature.energy.to_string());
+        let info = &creature.info;
+        self.status(inc!(y), "genotype:", &info.name);
+        let genome = format!("{} genes #{:016x}", info.genome_size, info.genome_hash);
+        self.status(inc!(y), "genome:", &genome);
+        self.line(inc!(y), &info.summary, false);
+        self.status(inc!(y), "action:", &creature.action);
+        match creature.vision {
+            Some(vision) => {
+                for (i, row) in vision_rows(grid, &vision, creature.sigil)
+                    .iter()
+                    .enumerate()
+                {
+                    let label = if i == 0 { "vision:" } else { "" };
+                    self.status(inc!(y), label, row);
+                }
+            }
+            None => self.status(inc!(y), "vision:", "none"),
+        }
+
+        let state = &creature.state;
+        for (i, pair) in state.registers.chunks(2).enumerate() {
+            let text: Vec<String> = pair
+                .iter()
+                .map(|(name, value)| format!("{}={:<6}", name, value))
+                .collect();
+            let label = if i == 0 { "registers:" } else { "" };
+            self.status(inc!(y), label, &text.join(" "));
+        }
+        let ip = state.ip.map_or("-".to_string(), |ip| ip.to_string());
+        self.status(inc!(y), "ip:", &ip);
+        let breaks: Vec<String> = debug.breakpoints.iter().map(|b| b.to_string()).collect();
+        self.status(inc!(y), "breaks:", &breaks.join(","));
+        self.status(inc!(y), "break on:", &debug.action_breakpoints.join(","));
+        inc!(y);
+
+        // show the part of the listing around the cursor
+        let (height, _) = self.right_pane.get_max_yx();
+        let rows = max(0, height - 1 - inc!(y)) as usize;
+        self.cursor = min(self.cursor, state.listing.len().saturating_sub(1));
+        let first = self.cursor.saturating_sub(rows / 2);
+        for row in 0..rows {
+            let address = first + row;
+            let text = match state.listing.get(address) {
+                Some(line) => {
+                    let ip = if state.ip == Some(address) { '>' } else { ' ' };
+                    let bp = if debug.breakpoints.contains(&address) {
+                        '*'
+                    } else {
+                        ' '
+                    };
+                    format!("{}{}{:>3} {}", bp, ip, address, line)
+                }
+                None => String::new(),
+            };
+            self.line(y + row as i32, &text, address == self.cursor);
+        }
+        let footer = self.keymap.footer(&[
+            (Action::Step, "step"),
+            (Action::Pause, "run"),
+            (Action::Debug, "exit"),
+        ]);
+        self.footer(&footer);
+    }
+
+    // a line of text across the status pane
+    fn line(&mut self, pos: i32, text: &str, highlight: bool) {
+        let (height, width) = self.right_pane.get_max_yx();
+        if pos >= height - 1 || pos <= 0 {
+            return;
+        }
+        let padded = format!("{:<1$}", text, width as usize);
+        self.right_pane.mv(pos, 1);
+        if highlight {
+            self.right_pane.attron(A_REVERSE);
+        }
+        self.right_pane.addnstr(padded, width as usize - 2);
+        if highlight {
+            self.right_pane.attroff(A_REVERSE);
+        }
+        self.right_pane.refresh();
+    }
+
+    // select a creature, paint or zoom and pan with the mouse
+    fn mouse_cmd(&mut self) -> GuiCmd {
+        let Ok(event) = pancurses::getmouse() else {
+            return GuiCmd::None;
+        };
+        let pos = Coord {
+            x: event.x,
+            y: event.y,
+        };
+        let (height, width) = self.left_pane.get_max_yx();
+        if pos.x < 0 || pos.y < 0 || pos.x >= width || pos.y >= height {
+            self.drag = None;
+            return GuiCmd::None;
+        }
+
+        // the wheel zooms around the pointer, with shift it pans up and down
+        let wheel = match event.bstate {
+            state if state & BUTTON4_PRESSED != 0 => -1,
+            state if state & BUTTON5_PRESSED != 0 => 1,
+            _ => 0,
+        };
+        if wheel != 0 {
+            let pane = Coord {
+                x: width,
+                y: height,
+            };
+            let size = self.grid_size;
+            match event.bstate & BUTTON_SHIFT != 0 {
+                true => self.viewport.pan(
+                    Coord {
+                        x: 0,
+                        y: wheel * PAN_STEP,
+                    },
+                    pane,
+                    size,
+                ),
+                false => self.viewport.zoom_at(pos, -wheel, pane, size),
+            }
+            // the pane changes size with the zoom
+            self.y_max = 0;
+            return GuiCmd::None;
+        }
+
+        // in half block mode a character shows a cell from each of its
+        // rows, a click selects the creature in either of them
+        let rows: Vec<Coord> = (0..self.viewport.rows)
+            .map(|row| self.viewport.to_world_row(pos, row))
+            .collect();
+        let cell = rows[0];
+        let Some(brush) = self.brush else {
+            let creature = rows.iter().copied().find(|coord| {
+                let cell = self.grid.as_ref().map(|grid| grid.get_cell(*coord));
+                matches!(cell, Some(Cell::Entity(..)))
+            });
+            return match event.bstate & BUTTON1_PRESSED != 0 {
+                true => GuiCmd::Select(creature.unwrap_or(cell)),
+                false => GuiCmd::None,
+            };
+        };
+        // paint the cells passed over since the last event of the drag
+        let from = match (event.bstate, self.drag) {
+            (state, _) if state & BUTTON1_PRESSED != 0 => cell,
+            (_, Some(from)) => from,
+            (_, None) => return GuiCmd::None,
+        };
+        self.drag = match event.bstate & BUTTON1_RELEASED != 0 {
+            true => None,
+            false => Some(cell),
+        };
+        // and painting covers both of them
+        let mut coords = line(from, cell);
+        for below in rows.iter().filter(|row| **row != cell) {
+            let offset = *below - cell;
+            coords.extend(line(from + offset, cell + offset));
+        }
+        GuiCmd::Paint(coords, brush)
+    }
+
+    // cycle through the brushes and back to no edit mode
+    fn next_brush(&mut self) {
+        self.brush = match self.brush {
+            None => Some(Brush::ALL[0]),
+            Some(brush) => Brush::ALL
+                .iter()
+                .position(|b| *b == brush)
+                .and_then(|i| Brush::ALL.get(i + 1))
+                .cloned(),
+        };
+        self.drag = None;
+    }
+
+    // the parts of the grid that are drawn with the current overlay and theme
+    fn parts(&self) -> SnapshotParts {
+        SnapshotParts {
+            heatmaps: self.overlay.is_some(),
+            energies: self.theme.shows_energy(),
+        }
+    }
+
+    // cycle through the heatmap overlays and back to none
+    fn next_overlay(&mut self) {
+        self.overlay = match self.overlay {
+            None => Some(Heatmap::ALL[0]),
+            Some(map) => Heatmap::ALL
+                .iter()
+                .position(|m| *m == map)
+                .and_then(|i| Heatmap::ALL.get(i + 1))
+                .cloned(),
+        };
+        // the cells must all be drawn again without the overlay
+        self.y_max = 0;
+    }
+
     fn footer(&mut self, text: &str) {
         let (height, width) = self.right_pane.get_max_yx();
         // center the text
@@ -269,31 +1086,83 @@ impl EyesGui {
     }
 
     fn show_help(&mut self) {
-        let help = "
-  -------------- COMMANDS ---------------
-
-                q:   quit
-                r:   reset world
-            space:   pause the world
-          up/down:   speed up/down
-       left/right:   grass up/down
-                h:   show this help
-
-  ---------------------------------------
-
-  See this file for world config:
-  $HOME/.config/eyes2/default-config.toml
+        let rule = |title: &str| format!("{:-^39}", format!(" {} ", title));
+        let mut lines = vec![String::new(), rule("COMMANDS"), String::new()];
+        lines.extend(self.keymap.help());
+        lines.extend([
+            String::new(),
+            "-".repeat(39),
+            String::new(),
+            "See this file for world config:".to_string(),
+            Settings::path(),
+            "and this file for the keys:".to_string(),
+            Keymap::path(),
+            String::new(),
+            "launch with -r to reset both files".to_string(),
+            "launch with --help for more options".to_string(),
+        ]);
 
-  launch with -r to reset the config file
-  launch with --help for more options";
-        self.help_pane.mvaddstr(1, 3, help);
+        // the pane fits the help, the paths may be long
+        let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0) as i32 + 6;
+        let height = lines.len() as i32 + 2;
+        // small screens show as much as fits
+        let (y_max, x_max) = self.window.get_max_yx();
+        let (height, width) = (min(height, y_max - 1), min(width, x_max - 3));
+        self.help_pane.resize(height, width);
+        self.help_pane.mvwin(1, 3);
+        self.help_pane.erase();
+        for (y, line) in lines.iter().enumerate() {
+            self.help_pane.mvaddstr(y as i32 + 1, 3, line);
+        }
         self.help_pane.refresh();
         self.help_pane.draw_box(0, 0);
         self.help_pane.getch();
+        // the help may cover more than the panes do
+        self.window.clear();
         self.y_max = 0; // force a resize which will redraw everything
     }
 }
 
+// the colour level of a heatmap value, anything above zero is at least 1
+fn heat_level(value: f32, max: f32) -> u8 {
+    let levels = HEAT_COLOURS.len() - 1;
+    match value > 0.0 && max > 0.0 {
+        true => ((value / max * levels as f32).ceil() as usize).clamp(1, levels) as u8,
+        false => 0,
+    }
+}
+
+// the vision around a creature as three rows of sigils, the creature is
+// in the middle
+fn vision_rows(grid: &WorldGrid, vision: &Vision, sigil: char) -> [String; 3] {
+    let cell_char = |cell: &Cell| match cell {
+        Cell::Empty => '.',
+        Cell::Grass(species, _) => grid.plant_sigil(*species),
+        Cell::Corpse(_) => 'x',
+        Cell::Entity(_, sigil) => *sigil,
+        Cell::Wall => '#',
+    };
+    // vision is in the order N, NE, E, SE, S, SW, W, NW
+    let row =
+        |cells: [usize; 3]| -> String { cells.iter().map(|i| cell_char(&vision[*i])).collect() };
+    [
+        row([7, 0, 1]),
+        format!(
+            "{}{}{}",
+            cell_char(&vision[6]),
+            sigil,
+            cell_char(&vision[2])
+        ),
+        row([5, 4, 3]),
+    ]
+}
+
+impl Default for EyesGui {
+    fn default() -> Self {
+        Self::new(Keymap::default())
+    }
+}
+
 impl Drop for EyesGui {
     fn drop(&mut self) {
         endwin();
@@ -0,0 +1,322 @@
+//! The keys of the GUI's commands.
+//!
+//! Every command has an entry in BINDINGS with its default key and the
+//! line shown for it in the help. The keys can be changed in the keymap
+//! file, which has a line such as `save = "w"` for each command. Keys are
+//! named by the character they type or one of the names in KEY_NAMES.
+//!
+use pancurses::Input;
+use std::collections::BTreeMap;
+use std::fmt;
+
+// a command of the GUI that is run by a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    Reset,
+    Pause,
+    Save,
+    Load,
+    SpeedUp,
+    SpeedDown,
+    GrassUp,
+    GrassDown,
+    Overlay,
+    Charts,
+    EditSettings,
+    Brush,
+    Theme,
+    ResetZoom,
+    Debug,
+    NextCreature,
+    Step,
+    RunUntil,
+    FastForward,
+    CursorUp,
+    CursorDown,
+    Breakpoint,
+    ActionBreakpoint,
+    Help,
+}
+
+#[derive(Debug)]
+pub struct Binding {
+    pub action: Action,
+    // the name of the command in the keymap file
+    pub name: &'static str,
+    pub key: &'static str,
+    pub help: &'static str,
+    // true if the key only works while the debugger is active
+    pub debug: bool,
+}
+
+const fn bind(
+    action: Action,
+    name: &'static str,
+    key: &'static str,
+    help: &'static str,
+) -> Binding {
+    Binding {
+        action,
+        name,
+        key,
+        help,
+        debug: false,
+    }
+}
+
+const fn debug(
+    action: Action,
+    name: &'static str,
+    key: &'static str,
+    help: &'static str,
+) -> Binding {
+    Binding {
+        debug: true,
+        ..bind(action, name, key, help)
+    }
+}
+
+// every command in the order they are listed in the help
+pub static BINDINGS: [Binding; 25] = [
+    bind(Action::Quit, "quit", "q", "quit"),
+    bind(Action::Reset, "reset", "r", "reset world"),
+    bind(Action::Pause, "pause", "space", "pause the world"),
+    bind(Action::Save, "save", "s", "save the world to world.yaml"),
+    bind(Action::Load, "load", "l", "load the world from world.yaml"),
+    bind(Action::SpeedUp, "speed_up", "up", "speed up"),
+    bind(Action::SpeedDown, "speed_down", "down", "speed down"),
+    bind(Action::GrassUp, "grass_up", "right", "grass up"),
+    bind(Action::GrassDown, "grass_down", "left", "grass down"),
+    bind(Action::Overlay, "overlay", "o", "cycle heatmap overlays"),
+    bind(Action::Charts, "charts", "c", "toggle population charts"),
+    bind(
+        Action::EditSettings,
+        "edit_settings",
+        "e",
+        "edit the settings",
+    ),
+    bind(Action::Brush, "brush", "m", "cycle mouse paint brushes"),
+    bind(Action::Theme, "theme", "t", "cycle themes"),
+    bind(Action::ResetZoom, "reset_zoom", "0", "reset the zoom"),
+    bind(Action::Debug, "debug", "d", "debug a creature"),
+    debug(
+        Action::NextCreature,
+        "next_creature",
+        "n",
+        "debug the next creature",
+    ),
+    bind(Action::Step, "step", ".", "step one tick (when paused)"),
+    bind(
+        Action::RunUntil,
+        "run_until",
+        "u",
+        "run until a condition, then pause",
+    ),
+    bind(
+        Action::FastForward,
+        "fast_forward",
+        "f",
+        "run N ticks fast, then pause",
+    ),
+    debug(
+        Action::CursorUp,
+        "cursor_up",
+        "[",
+        "move the listing cursor up",
+    ),
+    debug(
+        Action::CursorDown,
+        "cursor_down",
+        "]",
+        "move the listing cursor down",
+    ),
+    debug(
+        Action::Breakpoint,
+        "breakpoint",
+        "b",
+        "breakpoint at the cursor",
+    ),
+    debug(
+        Action::ActionBreakpoint,
+        "action_breakpoint",
+        "a",
+        "breakpoint on the last action",
+    ),
+    bind(Action::Help, "help", "h", "show this help"),
+];
+
+// the mouse is not in the keymap but is listed in the help
+pub const MOUSE_HELP: [(&str, &str); 2] = [
+    ("wheel", "zoom (shift+wheel to pan)"),
+    ("click", "inspect a creature"),
+];
+
+// nor are the keys for typing a value in the settings editor or the
+// run_until and fast_forward prompts
+pub const TYPING_HELP: [(&str, &str); 3] = [
+    ("enter", "edit a setting or accept a value"),
+    ("esc", "cancel a value or close the editor"),
+    ("backspace", "delete the last character typed"),
+];
+
+// the names of keys that do not type a printable character
+const KEY_NAMES: [(&str, Input); 10] = [
+    ("space", Input::Character(' ')),
+    ("tab", Input::Character('\t')),
+    ("up", Input::KeyUp),
+    ("down", Input::KeyDown),
+    ("left", Input::KeyLeft),
+    ("right", Input::KeyRight),
+    ("home", Input::KeyHome),
+    ("end", Input::KeyEnd),
+    ("pageup", Input::KeyPPage),
+    ("pagedown", Input::KeyNPage),
+];
+
+#[derive(Debug)]
+pub enum KeymapError {
+    // a name in the keymap file that is not a command
+    UnknownCommand(String),
+    // a key name that is not a character or in KEY_NAMES
+    UnknownKey(String, String),
+    // two commands with the same key
+    Conflict(String, String, String),
+}
+
+impl fmt::Display for KeymapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeymapError::UnknownCommand(name) => write!(f, "unknown command '{}'", name),
+            KeymapError::UnknownKey(name, key) => write!(f, "{}: unknown key '{}'", name, key),
+            KeymapError::Conflict(key, a, b) => {
+                write!(f, "'{}' is the key for both {} and {}", key, a, b)
+            }
+        }
+    }
+}
+
+impl std::error::Error for KeymapError {}
+
+// the key of every command
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    keys: Vec<(Input, &'static Binding)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::new(&BTreeMap::new()).expect("the default keys are valid")
+    }
+}
+
+impl Keymap {
+    // the keymap with the keys in the keymap file, the defaults with reset
+    pub fn load(reset: bool) -> Result<Keymap, Box<dyn std::error::Error>> {
+        let keys: BTreeMap<String, String> = match reset {
+            true => BTreeMap::new(),
+            false => confy::load("eyes2", "keymap")?,
+        };
+        let keymap = Keymap::new(&keys)?;
+        // write the file back with every command so that it is easy to edit
+        confy::store("eyes2", "keymap", keymap.names())?;
+        Ok(keymap)
+    }
+
+    // the full path of the keymap file
+    pub fn path() -> String {
+        confy::get_configuration_file_path("eyes2", "keymap")
+            .map_or_else(|e| e.to_string(), |p| p.display().to_string())
+    }
+
+    // the keymap with keys by command name, the rest keep their defaults
+    pub fn new(keys: &BTreeMap<String, String>) -> Result<Keymap, KeymapError> {
+        if let Some(name) = keys.keys().find(|n| !BINDINGS.iter().any(|b| b.name == *n)) {
+            return Err(KeymapError::UnknownCommand(name.clone()));
+        }
+
+        let mut keymap = Keymap { keys: Vec::new() };
+        for binding in BINDINGS.iter() {
+            let name = keys.get(binding.name).map_or(binding.key, |k| k.as_str());
+            let key = parse_key(name)
+                .ok_or_else(|| KeymapError::UnknownKey(binding.name.into(), name.into()))?;
+            if let Some((_, other)) = keymap.keys.iter().find(|(k, _)| *k == key) {
+                return Err(KeymapError::Conflict(
+                    name.into(),
+                    other.name.into(),
+                    binding.name.into(),
+                ));
+            }
+            keymap.keys.push((key, binding));
+        }
+        Ok(keymap)
+    }
+
+    // the command for a key, debugger commands only while debugging
+    pub fn action(&self, key: Input, debugging: bool) -> Option<Action> {
+        self.keys
+            .iter()
+            .find(|(k, binding)| *k == key && (debugging || !binding.debug))
+            .map(|(_, binding)| binding.action)
+    }
+
+    // the key of every command by name, as in the keymap file
+    pub fn names(&self) -> BTreeMap<String, String> {
+        self.keys
+            .iter()
+            .map(|(key, binding)| (binding.name.to_string(), key_name(*key)))
+            .collect()
+    }
+
+    // the name of the key of a command
+    pub fn key(&self, action: Action) -> String {
+        self.keys
+            .iter()
+            .find(|(_, binding)| binding.action == action)
+            .map_or_else(String::new, |(key, _)| key_name(*key))
+    }
+
+    // a footer naming the keys of some commands, e.g. " q: quit, h: help "
+    pub fn footer(&self, commands: &[(Action, &str)]) -> String {
+        let keys: Vec<String> = commands
+            .iter()
+            .map(|(action, text)| format!("{}: {}", self.key(*action), text))
+            .collect();
+        format!(" {} ", keys.join(", "))
+    }
+
+    // a line of help for every command, the mouse and the typing keys
+    pub fn help(&self) -> Vec<String> {
+        let line = |key: &str, help: &str| format!("{:>15}:   {}", key, help);
+        let keys = self
+            .keys
+            .iter()
+            .map(|(key, binding)| line(&key_name(*key), binding.help));
+        let fixed = MOUSE_HELP.iter().chain(TYPING_HELP.iter());
+        keys.chain(fixed.map(|(key, help)| line(key, help)))
+            .collect()
+    }
+}
+
+fn parse_key(name: &str) -> Option<Input> {
+    if let Some((_, key)) = KEY_NAMES.iter().find(|(n, _)| *n == name) {
+        return Some(*key);
+    }
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if !c.is_control() && c != ' ' => Some(Input::Character(c)),
+        _ => None,
+    }
+}
+
+fn key_name(key: Input) -> String {
+    match (KEY_NAMES.iter().find(|(_, k)| *k == key), key) {
+        (Some((name, _)), _) => name.to_string(),
+        (None, Input::Character(c)) => c.to_string(),
+        (None, key) => format!("{:?}", key),
+    }
+}
+
+#[path = "keymap_test.rs"]
+#[cfg(test)]
+mod test;
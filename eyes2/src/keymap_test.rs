@@ -0,0 +1,54 @@
+use super::*;
+
+fn keys(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+    pairs
+        .iter()
+        .map(|(name, key)| (name.to_string(), key.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_keymap() {
+    let keymap = Keymap::new(&keys(&[("save", "w"), ("pause", "p")])).unwrap();
+    assert_eq!(
+        keymap.action(Input::Character('w'), false),
+        Some(Action::Save)
+    );
+    assert_eq!(keymap.action(Input::Character('s'), false), None);
+    assert_eq!(keymap.action(Input::KeyUp, false), Some(Action::SpeedUp));
+    // debugger keys only work while debugging
+    assert_eq!(keymap.action(Input::Character('n'), false), None);
+    assert_eq!(
+        keymap.action(Input::Character('n'), true),
+        Some(Action::NextCreature)
+    );
+
+    let names = keymap.names();
+    assert_eq!(names["pause"], "p");
+    assert_eq!(names["speed_up"], "up");
+    assert_eq!(Keymap::new(&names).unwrap().names(), names);
+
+    // the help has a line for every command, the mouse and the typing keys
+    let help = keymap.help();
+    assert_eq!(
+        help.len(),
+        BINDINGS.len() + MOUSE_HELP.len() + TYPING_HELP.len()
+    );
+    assert_eq!(keymap.key(Action::Save), "w");
+    assert_eq!(keymap.key(Action::Pause), "p");
+    assert!(help.contains(&format!("{:>15}:   save the world to world.yaml", "w")));
+
+    let footer = keymap.footer(&[(Action::Step, "step"), (Action::Pause, "run")]);
+    assert_eq!(footer, " .: step, p: run ");
+}
+
+#[test]
+fn test_keymap_errors() {
+    let error = |pairs| Keymap::new(&keys(pairs)).unwrap_err().to_string();
+    assert_eq!(error(&[("jump", "j")]), "unknown command 'jump'");
+    assert_eq!(error(&[("save", "ctrl-s")]), "save: unknown key 'ctrl-s'");
+    assert_eq!(
+        error(&[("save", "q")]),
+        "'q' is the key for both quit and save"
+    );
+}
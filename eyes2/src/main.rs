@@ -1,15 +1,29 @@
 #![feature(test)]
 extern crate test;
 
+pub mod charts;
+pub mod debugger;
 pub mod gui;
+pub mod keymap;
+pub mod settings_editor;
+pub mod sweep;
+pub mod theme;
+pub mod viewport;
 use std::panic;
 
 use chrono::Utc;
-use clap::Parser;
-use eyes2_lib::{save_world, world::world::store::load_world, Settings, World, WorldGrid};
-use gui::{EyesGui, GuiCmd};
+use clap::{Parser, Subcommand};
+use debugger::Debugger;
+use eyes2_lib::entity::{assemble, disassemble_genotype, Creature, GilesGenotype};
+use eyes2_lib::world::{load_checkpoint, Autosave, RestartPolicy, SnapshotParts, Until};
+use eyes2_lib::{
+    load_world_file, save_stats, save_world, world::world::store::load_world, Cell, Plant,
+    Settings, World,
+};
+use gui::{EyesGui, Frame, GuiCmd};
+use keymap::Keymap;
 use pancurses::endwin;
-use panic_message;
+use std::path::Path;
 use std::{
     sync::mpsc::{self, Receiver, Sender},
     thread, time,
@@ -18,12 +32,80 @@ use std::{
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// reset settings to defaults
+    /// reset settings and keys to defaults
     #[arg(short, long)]
     reset: bool,
     /// use performance test settings (with GUI - for comparison with bench test)
     #[arg(short, long)]
     performance: bool,
+    /// carry on from the newest checkpoint that loads (see autosave_ticks
+    /// in the settings)
+    #[arg(long)]
+    resume: bool,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// print the genome of giles creatures in a saved world as assembly
+    Disassemble {
+        /// the saved world file
+        #[arg(short, long, default_value = "world.yaml")]
+        world: String,
+        /// only disassemble the creature with this id
+        id: Option<u64>,
+    },
+    /// assemble a program into a giles creature file
+    Assemble {
+        /// the assembly source file
+        source: String,
+        /// the creature file to write
+        #[arg(short, long, default_value = "creature.yaml")]
+        output: String,
+    },
+    /// run a world without the GUI until a condition is met or every
+    /// creature has died
+    Run {
+        /// stop when this is met: population<N, extinct=GENOTYPE or tick=T
+        /// (may be given more than once)
+        #[arg(short, long)]
+        until: Vec<Until>,
+        /// run the world saved in this file instead of a new world
+        #[arg(short, long)]
+        world: Option<String>,
+        /// carry on from the newest checkpoint that loads
+        #[arg(long, conflicts_with = "world")]
+        resume: bool,
+        /// save the world and its stats when it stops
+        #[arg(short, long)]
+        save: bool,
+        /// add the creature saved in this file (see assemble) at a random
+        /// place (may be given more than once)
+        #[arg(short, long)]
+        creature: Vec<String>,
+    },
+    /// run headless worlds for every combination of some settings values
+    /// and print a summary of how long the creatures survived
+    Sweep {
+        /// a setting and the values to try: name=a,b,c or
+        /// name=start..end[:step] (may be given more than once)
+        #[arg(short, long = "range", required = true)]
+        range: Vec<String>,
+        /// the number of runs of each combination, each with its own seed
+        #[arg(short, long, default_value_t = 3)]
+        seeds: u64,
+        /// the tick to stop a run at if the creatures are still alive
+        #[arg(short, long, default_value_t = 100_000)]
+        ticks: u64,
+        /// the settings to start from (a TOML file like the config), the
+        /// config if not given
+        #[arg(short, long)]
+        base: Option<String>,
+        /// also write the result of every run to this CSV file
+        #[arg(short, long)]
+        csv: Option<String>,
+    },
 }
 
 // Simulation speed control arrays.
@@ -33,63 +115,160 @@ struct Args {
 // These two arrays represent that tradeoff.
 const SPEED_TICKS: [u64; 10] = [1, 1, 1, 1, 10, 50, 100, 500, 1000, 100000];
 const SPEED_DELAY: [u64; 10] = [300, 10, 2, 1, 1, 1, 1, 1, 1, 0];
+// the speed used while fast forwarding
+const FAST_SPEED: usize = 10;
+
+// how the world runs between commands from the GUI
+#[derive(Default)]
+struct Control {
+    paused: bool,
+    // pause when this condition is met
+    until: Option<Until>,
+    // run at the fastest speed until the condition is met
+    fast: bool,
+    // the parts of the grid the GUI draws
+    parts: SnapshotParts,
+    // why the last condition was refused, shown until the world next
+    // runs or pauses
+    refused: Option<String>,
+}
+
+impl Control {
+    // run until a condition is met, any previous condition is dropped
+    fn run_until(&mut self, until: Until, fast: bool) {
+        (self.paused, self.until, self.fast) = (false, Some(until), fast);
+        self.refused = None;
+    }
+
+    fn pause(&mut self) {
+        (self.paused, self.until, self.fast) = (true, None, false);
+        self.refused = None;
+    }
+
+    // a line for the GUI's status pane
+    fn describe(&self) -> String {
+        if let Some(error) = &self.refused {
+            return error.clone();
+        }
+        match (&self.until, self.paused, self.fast) {
+            (_, true, _) => "paused".to_string(),
+            (Some(until), _, true) => format!("fast to {}", until),
+            (Some(until), _, false) => format!("until {}", until),
+            (None, _, _) => "running".to_string(),
+        }
+    }
+}
 
 fn main() {
     let args = Args::parse();
+    if let Some(command) = args.command {
+        if let Err(error) = run_command(command) {
+            eprintln!("Error: {}", error);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let settings = if args.performance {
         get_performance_settings()
     } else {
         get_settings(args.reset)
     };
+    let resume = match args.resume {
+        true => Some(resume_checkpoint().unwrap_or_else(|error| {
+            eprintln!("Error: {}", error);
+            std::process::exit(1);
+        })),
+        false => None,
+    };
+    let keymap = match Keymap::load(args.reset) {
+        Ok(keymap) => keymap,
+        Err(error) => {
+            eprintln!("Error in {}: {}", Keymap::path(), error);
+            std::process::exit(1);
+        }
+    };
 
     // catch any panics so that we can clean up curses before exiting
-    let result = panic::catch_unwind(|| {
-        world_loop(settings);
-    });
+    // (the resumed world is not used again after a panic)
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        world_loop(settings, keymap, resume);
+    }));
 
-    if result.is_err() {
+    if let Err(error) = result {
         endwin();
-        let error = result.unwrap_err();
         let msg = panic_message::panic_message(&error);
         println!("\n\nError: {:?}\n", msg);
     }
 }
 
-fn world_loop(mut settings: Settings) {
+fn world_loop(mut settings: Settings, keymap: Keymap, mut resume: Option<World>) {
     // setup channels for gui and world thread communications
     let (tx_grid, rx_grid) = mpsc::channel();
     let (tx_gui_cmd, rx_gui_cmd) = mpsc::channel::<GuiCmd>();
 
     // launch the gui thread
     thread::spawn(move || {
-        let mut gui = EyesGui::new();
+        let mut gui = EyesGui::new(keymap);
         gui.gui_loop(rx_grid, tx_gui_cmd).ok()
     });
 
-    let mut restarts = 0;
-    let mut paused = false;
+    let mut restarts = resume.as_ref().map_or(0, |world| world.grid.restarts);
+    let mut control = Control::default();
+    let mut debugger = Debugger::default();
+    // the best genomes of the last world to die out
+    let mut seeds = Vec::new();
 
     // outer loop continues until user quits or resets the world
     'outer: loop {
-        let mut world = World::new(settings.clone(), restarts);
-
-        world.grid.start_time = Utc::now();
-
-        world.populate();
+        let mut world = match resume.take() {
+            Some(world) => {
+                settings = world.config().clone();
+                world
+            }
+            None => {
+                let mut world = World::new(settings.clone(), restarts);
+                world.grid.start_time = Utc::now();
+                match settings.restart_policy {
+                    RestartPolicy::Fresh => world.populate(),
+                    _ => world.populate_from(std::mem::take(&mut seeds)),
+                }
+                world
+            }
+        };
+        debugger.retrace(&mut world);
+        let mut autosave = Autosave::new(&world);
 
         // inner loop runs until all creatures die
         'inner: loop {
-            let tick_result = do_tick(&mut world, &tx_grid, &rx_gui_cmd, &mut paused);
+            let tick_result = do_tick(
+                &mut world,
+                &tx_grid,
+                &rx_gui_cmd,
+                &mut control,
+                &mut debugger,
+                &mut settings,
+            );
             match tick_result {
                 Err(TickActions::Reset) => break 'inner,
                 Err(TickActions::Quit) => break 'outer,
                 Ok(()) => {}
             };
+            if autosave.due(&world) {
+                // a checkpoint that fails to save is tried again when the
+                // next one is due
+                autosave.save(&world, Path::new(".")).ok();
+            }
 
             if world.creature_count() == 0 {
                 break 'inner;
             }
         }
+        // a world that died out passes on its best genomes, one that the
+        // user reset starts afresh
+        if world.creature_count() == 0 {
+            seeds = world.seeds().to_vec();
+        }
         // copy variable config to the next world
         settings.grass_rate = world.grid.grass_rate;
         settings.speed = world.grid.speed;
@@ -104,42 +283,232 @@ enum TickActions {
 
 fn do_tick(
     world: &mut World,
-    tx_grid: &Sender<WorldGrid>,
+    tx_grid: &Sender<Frame>,
     rx_gui_cmd: &Receiver<GuiCmd>,
-    paused: &mut bool,
+    control: &mut Control,
+    debugger: &mut Debugger,
+    settings: &mut Settings,
 ) -> Result<(), TickActions> {
-    if (world.grid.ticks % SPEED_TICKS[world.grid.speed as usize - 1]) == 0 {
+    let speed = match control.fast {
+        true => FAST_SPEED,
+        false => world.grid.speed as usize,
+    };
+    // always poll when paused because single steps move ticks off the interval
+    if control.paused || world.grid.ticks.is_multiple_of(SPEED_TICKS[speed - 1]) {
         // Gui loop sends a command or GuiCmd::None every 100ms
         let next_cmd = rx_gui_cmd.try_recv();
 
-        if next_cmd.is_ok() {
-            match next_cmd.unwrap() {
+        if let Ok(next_cmd) = next_cmd {
+            let mut edit = None;
+            match next_cmd {
                 GuiCmd::Reset => return Err(TickActions::Reset),
                 GuiCmd::Quit => return Err(TickActions::Quit),
-                GuiCmd::Pause => *paused = !*paused,
+                GuiCmd::Pause if control.paused => {
+                    (control.paused, control.refused) = (false, None)
+                }
+                GuiCmd::Pause => control.pause(),
+                GuiCmd::RunUntil(until) => match until.check(world.config()) {
+                    Ok(()) => control.run_until(until, false),
+                    Err(error) => control.refused = Some(error),
+                },
+                GuiCmd::FastForward(ticks) => {
+                    control.run_until(Until::Tick(world.grid.ticks + ticks), true)
+                }
                 GuiCmd::SpeedUp => world.grid.increment_speed(true),
                 GuiCmd::SpeedDown => world.grid.increment_speed(false),
                 GuiCmd::GrassUp => world.grid.increment_grass_rate(true),
                 GuiCmd::GrassDown => world.grid.increment_grass_rate(false),
-                GuiCmd::Save => save_world(&world),
+                GuiCmd::Save => {
+                    save_world(world);
+                    // the GUI has nowhere to report an error, the world
+                    // itself is saved either way
+                    save_stats(world).ok();
+                }
                 GuiCmd::Load => {
                     *world = load_world();
+                    debugger.retrace(world);
+                }
+                GuiCmd::Debug => {
+                    debugger.toggle(world);
+                    // the world is paused on entering the debugger
+                    if debugger.is_active() {
+                        control.pause();
+                    }
+                }
+                GuiCmd::NextCreature => debugger.next_creature(world),
+                GuiCmd::Select(coord) => {
+                    if let Cell::Entity(id, _) = world.grid.get_cell(coord) {
+                        debugger.inspect(world, id);
+                        control.pause();
+                    }
+                }
+                GuiCmd::Paint(coords, brush) => {
+                    for coord in coords {
+                        world.paint(coord, brush);
+                    }
+                }
+                GuiCmd::Step if control.paused => world.tick(),
+                GuiCmd::Show(parts) => control.parts = parts,
+                GuiCmd::Breakpoint(address) => debugger.toggle_breakpoint(address),
+                GuiCmd::ActionBreakpoint => debugger.toggle_action_breakpoint(world),
+                GuiCmd::EditSettings => {
+                    // the speed and grass rate may have changed since
+                    settings.speed = world.grid.speed;
+                    settings.grass_rate = world.grid.grass_rate;
+                    edit = Some(settings.clone());
+                }
+                GuiCmd::ApplySettings(new, save) => {
+                    *settings = *new;
+                    world.set_config(settings);
+                    if save {
+                        settings.save(settings.clone());
+                    }
                 }
                 _ => {}
             };
-            tx_grid.send(world.grid.clone()).unwrap();
+            let (grid, cells) = world.snapshot(control.parts);
+            tx_grid
+                .send(Frame {
+                    grid,
+                    cells,
+                    debug: debugger.view(world),
+                    settings: edit,
+                    control: control.describe(),
+                    stats_interval: world.config().stats_interval,
+                })
+                .unwrap();
         }
 
-        thread::sleep(time::Duration::from_millis(
-            SPEED_DELAY[world.grid.speed as usize - 1],
-        ));
+        thread::sleep(time::Duration::from_millis(SPEED_DELAY[speed - 1]));
     }
-    if !*paused {
+    if !control.paused {
         world.tick();
+        if debugger.hit(world) || control.until.as_ref().is_some_and(|u| u.met(world)) {
+            control.pause();
+        }
     }
     Ok(())
 }
 
+// the command line tools that run without the GUI
+fn run_command(command: Command) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        Command::Disassemble { world, id } => {
+            let world = load_world_file(&world)?;
+            let mut creatures: Vec<&Creature> = world
+                .creatures()
+                .filter(|c| id.is_none_or(|id| c.id() == id))
+                .collect();
+            if let (Some(id), true) = (id, creatures.is_empty()) {
+                return Err(format!("no creature with id {} in the world", id).into());
+            }
+            creatures.sort_by_key(|c| c.id());
+            for creature in creatures {
+                if let Some(text) = disassemble_genotype(creature.genotype()) {
+                    let info = creature.genotype().describe();
+                    println!("; creature {} at {:?}", creature.id(), creature.coord());
+                    println!("; {} genome #{:016x}", info.summary, info.genome_hash);
+                    println!("{}", text);
+                }
+            }
+        }
+        Command::Assemble { source, output } => {
+            let genome = assemble(&std::fs::read_to_string(source)?)?;
+            let settings = Settings::load();
+            let genotype = GilesGenotype::from_genome(settings.clone(), genome);
+            let creature = Creature::detached(Box::new(genotype), settings);
+            let file = std::fs::File::create(output)?;
+            serde_yaml::to_writer(file, &creature)?;
+        }
+        Command::Run {
+            until,
+            world,
+            resume,
+            save,
+            creature,
+        } => {
+            let mut world = match world {
+                Some(path) => load_world_file(&path)?,
+                None if resume => resume_checkpoint()?,
+                None => {
+                    let mut world = World::new(Settings::load(), 0);
+                    world.populate();
+                    world
+                }
+            };
+            for path in creature {
+                let creature: Creature = serde_yaml::from_reader(std::fs::File::open(path)?)?;
+                world.introduce(creature);
+            }
+            for until in until.iter() {
+                until.check(world.config())?;
+            }
+            let stop = run_headless(&mut world, &until);
+            println!("stopped at tick {}: {}", world.grid.ticks, stop);
+            println!("population: {}", world.creature_count());
+            for (name, count) in world.genotype_counts() {
+                println!("  {}: {}", name, count);
+            }
+            if save {
+                save_world(&world);
+                save_stats(&world)?;
+            }
+        }
+        Command::Sweep {
+            range,
+            seeds,
+            ticks,
+            base,
+            csv,
+        } => {
+            let base = match base {
+                Some(path) => Settings::load_path(&path)?,
+                None => Settings::load(),
+            };
+            let ranges = range
+                .iter()
+                .map(|r| sweep::parse_range(r))
+                .collect::<Result<Vec<_>, _>>()?;
+            let combos = sweep::combinations(&ranges);
+            let settings = sweep::settings_for(&base, &ranges, &combos)?;
+            let runs = sweep::run_all(&settings, seeds, ticks);
+            print!("{}", sweep::summary(&ranges, &combos, &runs));
+            if let Some(path) = csv {
+                sweep::write_csv(&ranges, &combos, &runs, std::fs::File::create(path)?)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// tick the world until one of the conditions is met or every creature has
+// died and return the reason it stopped
+fn run_headless(world: &mut World, until: &[Until]) -> String {
+    let mut autosave = Autosave::new(world);
+    loop {
+        if let Some(met) = until.iter().find(|u| u.met(world)) {
+            return met.to_string();
+        }
+        if world.creature_count() == 0 {
+            return "extinct".to_string();
+        }
+        world.tick();
+        if autosave.due(world) {
+            if let Err(error) = autosave.save(world, Path::new(".")) {
+                eprintln!("Error saving a checkpoint: {}", error);
+            }
+        }
+    }
+}
+
+// the world in the newest checkpoint in the current directory
+fn resume_checkpoint() -> Result<World, String> {
+    let (world, path) =
+        load_checkpoint(Path::new(".")).ok_or("there is no checkpoint to resume from")?;
+    eprintln!("resuming from {}", path.display());
+    Ok(world)
+}
+
 fn get_settings(reset: bool) -> Settings {
     match reset {
         true => Settings::reset(),
@@ -158,7 +527,10 @@ fn get_performance_settings() -> Settings {
         creature_move_energy: 0,
         creature_idle_energy: 0,
         creature_move_rate: 0.001,
-        grass_energy: 0,
+        plants: vec![Plant {
+            energy: 0,
+            ..Plant::default()
+        }],
         speed: 10,
         creatures: vec![("random".to_string(), 50)],
         ..Settings::default()
@@ -181,11 +553,40 @@ mod tests {
         let world_ref = &mut world;
 
         bencher.iter(|| {
-            black_box({
-                for _ in 0..10000 {
-                    world_ref.tick()
-                }
-            });
+            for _ in 0..10000 {
+                world_ref.tick();
+            }
+            black_box(world_ref.grid.ticks)
         });
     }
+
+    #[test]
+    fn test_step_moves_one_tick() {
+        let mut settings = Settings {
+            speed: 10,
+            grass_rate: 50,
+            ..Settings::default()
+        };
+        let mut world = World::new(settings.clone(), 0);
+        world.populate();
+        let (tx_grid, _rx_grid) = mpsc::channel();
+        let (tx_gui_cmd, rx_gui_cmd) = mpsc::channel();
+        let mut control = Control::default();
+        control.pause();
+        let mut debugger = Debugger::default();
+
+        for ticks in 1..=3 {
+            tx_gui_cmd.send(GuiCmd::Step).unwrap();
+            let tick = do_tick(
+                &mut world,
+                &tx_grid,
+                &rx_gui_cmd,
+                &mut control,
+                &mut debugger,
+                &mut settings,
+            );
+            assert!(tick.is_ok());
+            assert_eq!(world.grid.ticks, ticks);
+        }
+    }
 }
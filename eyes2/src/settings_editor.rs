@@ -0,0 +1,148 @@
+//! A form for editing the settings from the GUI.
+//!
+//! Every field of the settings is listed with its value as a line of YAML.
+//! A new value is checked before it is accepted. Live fields apply to the
+//! running world at once, the rest (marked with *) when the world is next
+//! reset. The settings can also be saved as the default config.
+//!
+//! The editor is saved with the key of the save command and closed with the
+//! keys of the quit and edit_settings commands (see keymap.rs).
+//!
+use crate::keymap::{Action, Keymap};
+use eyes2_lib::settings::SettingsField;
+use eyes2_lib::Settings;
+use pancurses::Input;
+
+// what the GUI should do after a key press in the editor
+pub enum EditorAction {
+    None,
+    Close,
+    // send the edited settings to the world
+    Apply(Settings),
+    // apply the settings and save them as the default config
+    Save(Settings),
+}
+
+pub struct SettingsEditor {
+    settings: Settings,
+    fields: Vec<SettingsField>,
+    cursor: usize,
+    // the text typed so far while a field is being edited
+    input: Option<String>,
+    // the result of the last edit or an explanation of the keys
+    message: String,
+    // the explanation of the keys
+    help: String,
+}
+
+impl SettingsEditor {
+    pub fn new(settings: Settings, keymap: &Keymap) -> SettingsEditor {
+        let help = format!(
+            "enter: edit, {}: save as default, esc or {}: close",
+            keymap.key(Action::Save),
+            keymap.key(Action::EditSettings)
+        );
+        SettingsEditor {
+            fields: settings.fields(),
+            settings,
+            cursor: 0,
+            input: None,
+            message: help.clone(),
+            help,
+        }
+    }
+
+    // handle a key, action is the command the keymap has for it
+    pub fn key(&mut self, key: Input, action: Option<Action>) -> EditorAction {
+        if let Some(input) = self.input.as_mut() {
+            match key {
+                Input::Character('\n') | Input::KeyEnter => return self.commit(),
+                Input::Character('\x1b') => {
+                    self.input = None;
+                    self.message = self.help.clone();
+                }
+                Input::KeyBackspace | Input::Character('\x7f') | Input::Character('\x08') => {
+                    input.pop();
+                }
+                Input::Character(c) if !c.is_control() => input.push(c),
+                _ => {}
+            }
+            return EditorAction::None;
+        }
+
+        let last = self.fields.len() - 1;
+        match action {
+            Some(Action::Save) => {
+                self.message = "saved as the default config".to_string();
+                return EditorAction::Save(self.settings.clone());
+            }
+            Some(Action::Quit | Action::EditSettings) => return EditorAction::Close,
+            _ => {}
+        }
+        match key {
+            Input::KeyUp => self.cursor = self.cursor.saturating_sub(1),
+            Input::KeyDown => self.cursor = (self.cursor + 1).min(last),
+            Input::KeyPPage => self.cursor = self.cursor.saturating_sub(10),
+            Input::KeyNPage => self.cursor = (self.cursor + 10).min(last),
+            Input::Character('\n') | Input::KeyEnter => {
+                self.input = Some(self.fields[self.cursor].value.clone());
+            }
+            Input::Character('\x1b') => return EditorAction::Close,
+            _ => {}
+        }
+        EditorAction::None
+    }
+
+    // the lines of the form for a window of the given size as (text, highlight)
+    pub fn lines(&self, height: usize, width: usize) -> Vec<(String, bool)> {
+        let mut lines = vec![("SETTINGS (* applies on the next reset)".to_string(), false)];
+
+        // scroll to keep the cursor on screen below the title and above
+        // the message
+        let rows = height.saturating_sub(3).max(1);
+        let top = self.cursor.saturating_sub(rows - 1);
+        let name_width = self.fields.iter().map(|f| f.name.len()).max().unwrap_or(0);
+        for (i, field) in self.fields.iter().enumerate().skip(top).take(rows) {
+            let value = match (&self.input, i == self.cursor) {
+                (Some(input), true) => format!("{}_", input),
+                _ => field.value.clone(),
+            };
+            let mark = if field.live { ' ' } else { '*' };
+            let text = format!("{}{:<3$} {}", mark, field.name, value, name_width);
+            lines.push((fit(&text, width), i == self.cursor));
+        }
+
+        while lines.len() < height.saturating_sub(1) {
+            lines.push((String::new(), false));
+        }
+        lines.push((fit(&self.message, width), false));
+        lines
+    }
+
+    fn commit(&mut self) -> EditorAction {
+        let name = self.fields[self.cursor].name.clone();
+        let value = self.input.clone().unwrap_or_default();
+        match self.settings.set_field(&name, &value) {
+            Ok(()) => {
+                self.fields = self.settings.fields();
+                self.input = None;
+                let when = match self.fields[self.cursor].live {
+                    true => "now",
+                    false => "on the next reset",
+                };
+                self.message = format!("{} applies {}", name, when);
+                EditorAction::Apply(self.settings.clone())
+            }
+            // keep the input so that it can be corrected
+            Err(error) => {
+                self.message = error.to_string();
+                EditorAction::None
+            }
+        }
+    }
+}
+
+// pad or cut text to exactly width characters
+fn fit(text: &str, width: usize) -> String {
+    format!("{:<1$}", text, width).chars().take(width).collect()
+}
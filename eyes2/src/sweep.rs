@@ -0,0 +1,248 @@
+//! Run headless worlds for every combination of a few settings values.
+//!
+//! Each range names a settings field and the values to try, either as a
+//! list (`grass_rate=50,70,90`) or as an inclusive range with an optional
+//! step (`grass_rate=50..90:10`). Every combination is run once for each
+//! seed until the creatures die out or the tick limit is reached. The
+//! summary has a row per combination with the mean survival time and the
+//! mean final population of each genotype.
+//!
+//! The seed is used for the random number generators of the world but the
+//! order the creatures take their turns is not seeded, so runs with the
+//! same seed are alike rather than identical.
+//!
+use eyes2_lib::world::Until;
+use eyes2_lib::{Settings, World};
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt::Write as _;
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+// a settings field and the values to try for it as lines of YAML
+#[derive(Debug, Clone, PartialEq)]
+pub struct Range {
+    pub name: String,
+    pub values: Vec<String>,
+}
+
+// the result of one run of one combination
+pub struct Run {
+    pub combination: usize,
+    pub seed: u64,
+    // the tick the creatures died out or the tick limit
+    pub ticks: u64,
+    pub survived: bool,
+    pub genotypes: BTreeMap<String, usize>,
+}
+
+pub fn parse_range(text: &str) -> Result<Range, String> {
+    let (name, values) = text
+        .split_once('=')
+        .ok_or_else(|| format!("'{}' is not name=values", text))?;
+    let values = match values.split_once("..") {
+        Some((start, rest)) => {
+            let (end, step) = rest.split_once(':').unwrap_or((rest, "1"));
+            steps(start.trim(), end.trim(), step.trim())?
+        }
+        None => values.split(',').map(|v| v.trim().to_string()).collect(),
+    };
+    if values.iter().any(|v| v.is_empty()) {
+        return Err(format!("'{}' has an empty value", text));
+    }
+    Ok(Range {
+        name: name.trim().to_string(),
+        values,
+    })
+}
+
+// the values from start to end inclusive, whole numbers if all three are
+fn steps(start: &str, end: &str, step: &str) -> Result<Vec<String>, String> {
+    let number = |text: &str| {
+        text.parse::<f64>()
+            .map_err(|_| format!("'{}' is not a number", text))
+    };
+    let (first, last, by) = (number(start)?, number(end)?, number(step)?);
+    if by <= 0.0 || last < first {
+        return Err(format!("{}..{}:{} is an empty range", start, end, step));
+    }
+    let whole = [start, end, step].iter().all(|t| t.parse::<i64>().is_ok());
+    let count = ((last - first) / by + 1e-9).floor() as usize + 1;
+    Ok((0..count)
+        .map(|i| {
+            let value = first + i as f64 * by;
+            match whole {
+                true => format!("{}", value as i64),
+                // avoid printing float noise such as 0.30000000000000004
+                false => format!("{:.6}", value)
+                    .trim_end_matches('0')
+                    .trim_end_matches('.')
+                    .to_string(),
+            }
+        })
+        .collect())
+}
+
+// every combination of the values of the ranges, the first range changes
+// slowest
+pub fn combinations(ranges: &[Range]) -> Vec<Vec<String>> {
+    ranges.iter().fold(vec![vec![]], |combos, range| {
+        combos
+            .iter()
+            .flat_map(|combo| {
+                range.values.iter().map(move |value| {
+                    let mut combo = combo.clone();
+                    combo.push(value.clone());
+                    combo
+                })
+            })
+            .collect()
+    })
+}
+
+// the settings for each combination, checked before anything is run
+pub fn settings_for(
+    base: &Settings,
+    ranges: &[Range],
+    combos: &[Vec<String>],
+) -> Result<Vec<Settings>, String> {
+    combos
+        .iter()
+        .map(|combo| {
+            let mut settings = base.clone();
+            for (range, value) in ranges.iter().zip(combo) {
+                settings
+                    .set_field(&range.name, value)
+                    .map_err(|e| e.to_string())?;
+            }
+            Ok(settings)
+        })
+        .collect()
+}
+
+// run every combination with every seed on all the cores there are
+pub fn run_all(settings: &[Settings], seeds: u64, ticks: u64) -> Vec<Run> {
+    let jobs: Vec<(usize, u64)> = (0..settings.len())
+        .flat_map(|combination| (0..seeds).map(move |seed| (combination, seed)))
+        .collect();
+    let next = AtomicUsize::new(0);
+    let runs = Mutex::new(Vec::new());
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+
+    thread::scope(|scope| {
+        for _ in 0..threads.min(jobs.len()) {
+            scope.spawn(|| {
+                while let Some(&(combination, seed)) = jobs.get(next.fetch_add(1, Ordering::SeqCst))
+                {
+                    let run = run_one(&settings[combination], combination, seed, ticks);
+                    let mut runs = runs.lock().unwrap();
+                    runs.push(run);
+                    eprint!("\rrun {}/{}", runs.len(), jobs.len());
+                }
+            });
+        }
+    });
+    eprintln!();
+
+    let mut runs = runs.into_inner().unwrap();
+    runs.sort_by_key(|run| (run.combination, run.seed));
+    runs
+}
+
+fn run_one(settings: &Settings, combination: usize, seed: u64, ticks: u64) -> Run {
+    // the world's generators are made from the thread's generator
+    fastrand::seed(seed);
+    // runs in parallel would overwrite each other's checkpoints
+    let settings = Settings {
+        autosave_ticks: 0,
+        autosave_minutes: 0,
+        ..settings.clone()
+    };
+    let mut world = World::new(settings, 0);
+    world.populate();
+    crate::run_headless(&mut world, &[Until::Tick(ticks)]);
+    Run {
+        combination,
+        seed,
+        ticks: world.grid.ticks,
+        survived: world.creature_count() > 0,
+        genotypes: world.genotype_counts(),
+    }
+}
+
+// a row for each combination with the mean of its runs
+pub fn summary(ranges: &[Range], combos: &[Vec<String>], runs: &[Run]) -> String {
+    let genotypes: BTreeSet<&String> = runs.iter().flat_map(|r| r.genotypes.keys()).collect();
+    let mut header: Vec<String> = ranges.iter().map(|r| r.name.clone()).collect();
+    header.extend(["runs", "survived", "mean ticks"].map(String::from));
+    header.extend(genotypes.iter().map(|g| g.to_string()));
+
+    let mut rows = vec![header];
+    for (i, combo) in combos.iter().enumerate() {
+        let mine: Vec<&Run> = runs.iter().filter(|r| r.combination == i).collect();
+        let n = mine.len().max(1) as f64;
+        let mean = |value: &dyn Fn(&Run) -> f64| mine.iter().map(|r| value(r)).sum::<f64>() / n;
+        let mut row = combo.clone();
+        row.push(mine.len().to_string());
+        row.push(mine.iter().filter(|r| r.survived).count().to_string());
+        row.push(format!("{:.0}", mean(&|r| r.ticks as f64)));
+        for genotype in genotypes.iter() {
+            let count = mean(&|r| r.genotypes.get(*genotype).copied().unwrap_or(0) as f64);
+            row.push(format!("{:.1}", count));
+        }
+        rows.push(row);
+    }
+
+    // line the columns up
+    let widths: Vec<usize> = (0..rows[0].len())
+        .map(|c| rows.iter().map(|row| row[c].len()).max().unwrap_or(0))
+        .collect();
+    let mut text = String::new();
+    for row in rows {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:>1$}", cell, width))
+            .collect();
+        writeln!(text, "{}", cells.join("  ")).unwrap();
+    }
+    text
+}
+
+// a line for every run
+pub fn write_csv(
+    ranges: &[Range],
+    combos: &[Vec<String>],
+    runs: &[Run],
+    mut writer: impl Write,
+) -> Result<(), Box<dyn Error>> {
+    let genotypes: BTreeSet<&String> = runs.iter().flat_map(|r| r.genotypes.keys()).collect();
+    let mut header: Vec<String> = ranges.iter().map(|r| r.name.clone()).collect();
+    header.extend(["seed", "ticks", "survived"].map(String::from));
+    header.extend(genotypes.iter().map(|g| g.to_string()));
+    writeln!(writer, "{}", header.join(","))?;
+
+    for run in runs {
+        let mut row = combos[run.combination].clone();
+        row.push(run.seed.to_string());
+        row.push(run.ticks.to_string());
+        row.push(run.survived.to_string());
+        for genotype in genotypes.iter() {
+            row.push(
+                run.genotypes
+                    .get(*genotype)
+                    .copied()
+                    .unwrap_or(0)
+                    .to_string(),
+            );
+        }
+        writeln!(writer, "{}", row.join(","))?;
+    }
+    Ok(())
+}
+
+#[path = "sweep_test.rs"]
+#[cfg(test)]
+mod test;
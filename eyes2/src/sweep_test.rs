@@ -0,0 +1,38 @@
+use super::*;
+
+#[test]
+fn test_parse_range() {
+    let range = |text| parse_range(text).unwrap().values;
+    assert_eq!(range("grass_rate=50..90:20"), ["50", "70", "90"]);
+    assert_eq!(range("speed=1..3"), ["1", "2", "3"]);
+    assert_eq!(
+        range("creature_move_rate=0.1..0.3:0.1"),
+        ["0.1", "0.2", "0.3"]
+    );
+    assert_eq!(range("grass_model = spread, random"), ["spread", "random"]);
+    assert_eq!(
+        parse_range("grass_model = spread").unwrap().name,
+        "grass_model"
+    );
+    assert!(parse_range("grass_rate").is_err());
+    assert!(parse_range("grass_rate=9..1").is_err());
+    assert!(parse_range("grass_rate=1,,2").is_err());
+}
+
+#[test]
+fn test_combinations() {
+    let ranges = [
+        parse_range("a=1,2").unwrap(),
+        parse_range("b=x,y,z").unwrap(),
+    ];
+    let combos = combinations(&ranges);
+    assert_eq!(combos.len(), 6);
+    assert_eq!(combos[0], ["1", "x"]);
+    assert_eq!(combos[5], ["2", "z"]);
+
+    // every value is checked before anything runs
+    let base = Settings::default();
+    let ranges = [parse_range("grass_rate=50,500").unwrap()];
+    let error = settings_for(&base, &ranges, &combinations(&ranges)).unwrap_err();
+    assert!(error.contains("grass_rate"));
+}
@@ -0,0 +1,198 @@
+//! The themes used to draw the world in the GUI.
+//!
+//! The classic theme draws ASCII sigils with the 8 basic colours. The
+//! unicode theme draws glyphs in the 256 colour palette and shows the
+//! energy of each creature as its brightness. The half block theme draws
+//! every cell as a block of colour with two rows of the world in each
+//! character so that a square world looks square and more of it fits on
+//! the screen.
+//!
+//! Colours are picked from the 6x6x6 colour cube of 256 colour terminals,
+//! or the nearest of the 8 basic colours on other terminals. Curses has no
+//! way to ask for 24 bit colours so the cube is as fine as it gets.
+//!
+use eyes2_lib::{Cell, WorldGrid};
+use pancurses::{COLORS, COLOR_PAIRS};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    Classic,
+    Unicode,
+    HalfBlock,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::Classic, Theme::Unicode, Theme::HalfBlock];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Theme::Classic => "classic",
+            Theme::Unicode => "unicode",
+            Theme::HalfBlock => "half block",
+        }
+    }
+
+    pub fn next(&self) -> Theme {
+        let i = Theme::ALL.iter().position(|t| t == self).unwrap_or(0);
+        Theme::ALL[(i + 1) % Theme::ALL.len()]
+    }
+
+    // the rows of the world drawn in each row of characters
+    pub fn rows(&self) -> i32 {
+        match self {
+            Theme::HalfBlock => 2,
+            _ => 1,
+        }
+    }
+
+    // true if the look of a cell changes with the energy of its creature
+    pub fn shows_energy(&self) -> bool {
+        *self != Theme::Classic
+    }
+}
+
+// a colour in the 6x6x6 colour cube, each level is 0 to 5
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+pub const BLACK: Rgb = Rgb(0, 0, 0);
+pub const WHITE: Rgb = Rgb(5, 5, 5);
+const WALL: Rgb = Rgb(2, 2, 2);
+const CORPSE: Rgb = Rgb(3, 0, 0);
+// each species of creature is drawn with one of these colours
+const SPECIES: [Rgb; 6] = [
+    Rgb(5, 5, 0),
+    Rgb(0, 5, 5),
+    Rgb(5, 0, 5),
+    Rgb(5, 1, 1),
+    Rgb(1, 5, 1),
+    Rgb(1, 2, 5),
+];
+// each species of plant is drawn with one of these glyphs and colours
+const PLANT_GLYPHS: [char; 4] = ['♣', '✿', '♠', '❀'];
+const PLANTS: [Rgb; 4] = [Rgb(0, 4, 0), Rgb(3, 5, 0), Rgb(0, 3, 1), Rgb(2, 4, 2)];
+// heatmap overlay colours from cold to hot
+pub const HEAT: [Rgb; 5] = [
+    BLACK,
+    Rgb(0, 0, 3),
+    Rgb(0, 3, 0),
+    Rgb(4, 4, 0),
+    Rgb(5, 0, 0),
+];
+// the brightness of a creature with no energy
+const DIMMEST: f32 = 0.35;
+// the number of brightness levels used to show energy
+const ENERGY_BANDS: i32 = 6;
+
+impl Rgb {
+    // the colour made brighter or darker, 1.0 leaves it unchanged
+    pub fn scale(self, brightness: f32) -> Rgb {
+        let level = |l: u8| (l as f32 * brightness).round().clamp(0.0, 5.0) as u8;
+        Rgb(level(self.0), level(self.1), level(self.2))
+    }
+
+    // the number of this colour on a terminal with the given number of
+    // colours
+    pub fn index(self, colours: i32) -> i16 {
+        match colours >= 256 {
+            true => 16 + 36 * self.0 as i16 + 6 * self.1 as i16 + self.2 as i16,
+            // the basic colours have a bit each for red, green and blue
+            false => {
+                let bit = |l: u8, bit: i16| if l >= 3 { bit } else { 0 };
+                bit(self.0, 1) | bit(self.1, 2) | bit(self.2, 4)
+            }
+        }
+    }
+}
+
+// the glyph drawn for a cell by the unicode theme
+pub fn glyph(cell: Cell) -> char {
+    match cell {
+        Cell::Empty => ' ',
+        Cell::Grass(species, _) => PLANT_GLYPHS[species as usize % PLANT_GLYPHS.len()],
+        Cell::Corpse(_) => '†',
+        Cell::Entity(_, sigil) => sigil,
+        Cell::Wall => '█',
+    }
+}
+
+// the energy of the most energetic creature in the grid
+pub fn max_energy(grid: &WorldGrid) -> i32 {
+    grid.energies.values().map(|(_, e)| *e).max().unwrap_or(0)
+}
+
+// the brightness level of a creature's energy compared with the most
+// energetic creature, the creature is only drawn again when it changes
+pub fn energy_band(energy: i32, max_energy: i32) -> i32 {
+    match max_energy > 0 {
+        true => {
+            (energy.clamp(0, max_energy) as i64 * (ENERGY_BANDS - 1) as i64 / max_energy as i64)
+                as i32
+        }
+        false => ENERGY_BANDS - 1,
+    }
+}
+
+// the colour of a cell, creatures are dimmer the less energy they have
+// compared with the most energetic creature (max_energy)
+pub fn colour(grid: &WorldGrid, cell: Cell, max_energy: i32) -> Rgb {
+    match cell {
+        Cell::Empty => BLACK,
+        Cell::Grass(species, _) => PLANTS[species as usize % PLANTS.len()],
+        Cell::Corpse(_) => CORPSE,
+        Cell::Entity(id, _) => {
+            // creatures born since the last clustering have no species
+            let colour = match grid.species_of.get(&id) {
+                Some(species) => SPECIES[*species as usize % SPECIES.len()],
+                None => WHITE,
+            };
+            let energy = grid.energies.get(&id).map_or(max_energy, |(_, e)| *e);
+            let ratio = energy_band(energy, max_energy) as f32 / (ENERGY_BANDS - 1) as f32;
+            colour.scale(DIMMEST + (1.0 - DIMMEST) * ratio)
+        }
+        Cell::Wall => WALL,
+    }
+}
+
+// colour pairs for foreground and background colours, made as they are
+// needed after the pairs used by the classic theme
+pub struct Palette {
+    pairs: HashMap<(i16, i16), i16>,
+    first: i16,
+    last: i16,
+    colours: i32,
+}
+
+impl Palette {
+    // call after start_color so that the terminal's colours are known
+    pub fn new(first: i16) -> Palette {
+        Palette {
+            pairs: HashMap::new(),
+            first,
+            last: COLOR_PAIRS().clamp(0, i16::MAX as i32) as i16 - 1,
+            colours: COLORS(),
+        }
+    }
+
+    // the colour pair for fg on bg, None if the terminal has too few pairs
+    // and the pairs must be made again with a full redraw
+    pub fn pair(&mut self, fg: Rgb, bg: Rgb) -> Option<i16> {
+        let key = (fg.index(self.colours), bg.index(self.colours));
+        if let Some(pair) = self.pairs.get(&key) {
+            return Some(*pair);
+        }
+        let pair = self.first + self.pairs.len() as i16;
+        if pair > self.last {
+            self.pairs.clear();
+            return None;
+        }
+        pancurses::init_pair(pair, key.0, key.1);
+        self.pairs.insert(key, pair);
+        Some(pair)
+    }
+}
+
+#[path = "theme_test.rs"]
+#[cfg(test)]
+mod test;
@@ -0,0 +1,29 @@
+use super::*;
+
+#[test]
+fn test_colour_index() {
+    assert_eq!(BLACK.index(256), 16);
+    assert_eq!(WHITE.index(256), 231);
+    assert_eq!(Rgb(5, 1, 1).index(256), 16 + 180 + 6 + 1);
+    // the nearest basic colour
+    assert_eq!(Rgb(5, 1, 1).index(8), 1);
+    assert_eq!(Rgb(0, 5, 5).index(8), 6);
+    assert_eq!(WHITE.index(8), 7);
+}
+
+#[test]
+fn test_scale() {
+    assert_eq!(Rgb(5, 4, 0).scale(0.5), Rgb(3, 2, 0));
+    assert_eq!(Rgb(5, 4, 0).scale(2.0), Rgb(5, 5, 0));
+    assert_eq!(Theme::HalfBlock.next(), Theme::Classic);
+}
+
+#[test]
+fn test_energy_band() {
+    assert_eq!(energy_band(0, 1000), 0);
+    assert_eq!(energy_band(499, 1000), 2);
+    assert_eq!(energy_band(1000, 1000), 5);
+    assert_eq!(energy_band(-5, 1000), 0);
+    // with no energy to compare with creatures are drawn at full brightness
+    assert_eq!(energy_band(10, 0), 5);
+}
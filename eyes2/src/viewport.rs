@@ -0,0 +1,99 @@
+//! The part of the world shown in the GUI's world pane.
+//!
+//! At zoom level n each cell of the world is drawn as an n by n block of
+//! characters, the origin is the world cell in the top left corner of the
+//! pane. Themes that draw with half blocks fit two rows of the world in
+//! each row of characters so a cell is then n by n half rows.
+//!
+use direction::Coord;
+use std::cmp::max;
+
+pub const MAX_ZOOM: i32 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport {
+    pub zoom: i32,
+    pub origin: Coord,
+    // the rows (or half rows) in each row of characters, 1 or 2
+    pub rows: i32,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Viewport {
+            zoom: 1,
+            origin: Coord { x: 0, y: 0 },
+            rows: 1,
+        }
+    }
+}
+
+impl Viewport {
+    // the world cell drawn at a position in the pane (the upper one when
+    // there are two rows in each character)
+    pub fn to_world(&self, pos: Coord) -> Coord {
+        self.to_world_row(pos, 0)
+    }
+
+    // the world cell drawn in one of the rows of a character
+    pub fn to_world_row(&self, pos: Coord, row: i32) -> Coord {
+        Coord {
+            x: self.origin.x + pos.x / self.zoom,
+            y: self.origin.y + (pos.y * self.rows + row) / self.zoom,
+        }
+    }
+
+    // the size of the pane needed to show a whole world
+    pub fn pane_size(&self, size: i32) -> Coord {
+        Coord {
+            x: size * self.zoom,
+            y: (size * self.zoom + self.rows - 1) / self.rows,
+        }
+    }
+
+    // zoom in (step > 0) or out keeping the cell at pos under it, pane is
+    // the size of the pane and size the size of the world
+    pub fn zoom_at(&mut self, pos: Coord, step: i32, pane: Coord, size: i32) {
+        let cell = self.to_world(pos);
+        self.zoom = (self.zoom + step).clamp(1, MAX_ZOOM);
+        self.origin = Coord {
+            x: cell.x - pos.x / self.zoom,
+            y: cell.y - pos.y * self.rows / self.zoom,
+        };
+        self.clamp(pane, size);
+    }
+
+    // move the origin by a number of world cells
+    pub fn pan(&mut self, delta: Coord, pane: Coord, size: i32) {
+        self.origin += delta;
+        self.clamp(pane, size);
+    }
+
+    // keep as much of the world in the pane as will fit
+    fn clamp(&mut self, pane: Coord, size: i32) {
+        let limit = |pane: i32| max(0, size - pane / self.zoom);
+        self.origin = Coord {
+            x: self.origin.x.clamp(0, limit(pane.x)),
+            y: self.origin.y.clamp(0, limit(pane.y * self.rows)),
+        };
+    }
+}
+
+// the cells on a straight line between two cells, including both ends
+pub fn line(from: Coord, to: Coord) -> Vec<Coord> {
+    let delta = to - from;
+    let steps = max(delta.x.abs(), delta.y.abs());
+    if steps == 0 {
+        return vec![from];
+    }
+    (0..=steps)
+        .map(|i| Coord {
+            x: from.x + (delta.x as f32 * i as f32 / steps as f32).round() as i32,
+            y: from.y + (delta.y as f32 * i as f32 / steps as f32).round() as i32,
+        })
+        .collect()
+}
+
+#[path = "viewport_test.rs"]
+#[cfg(test)]
+mod test;
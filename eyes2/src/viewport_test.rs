@@ -0,0 +1,41 @@
+use super::*;
+
+#[test]
+fn test_zoom_keeps_the_cell_under_the_pointer() {
+    let pane = Coord { x: 40, y: 40 };
+    let mut view = Viewport::default();
+    let pointer = Coord { x: 30, y: 10 };
+
+    view.zoom_at(pointer, 1, pane, 40);
+    assert_eq!(view.zoom, 2);
+    assert_eq!(view.to_world(pointer), Coord { x: 30, y: 10 });
+    // the view stops at the edges of the world
+    assert_eq!(view.origin, Coord { x: 15, y: 5 });
+    view.pan(Coord { x: 100, y: -100 }, pane, 40);
+    assert_eq!(view.origin, Coord { x: 20, y: 0 });
+
+    view.zoom_at(pointer, -5, pane, 40);
+    assert_eq!(view, Viewport::default());
+}
+
+#[test]
+fn test_line() {
+    let from = Coord { x: 0, y: 0 };
+    assert_eq!(line(from, from), [from]);
+    assert_eq!(
+        line(from, Coord { x: 4, y: 2 }),
+        [(0, 0), (1, 1), (2, 1), (3, 2), (4, 2)].map(|(x, y)| Coord { x, y })
+    );
+}
+
+#[test]
+fn test_half_rows() {
+    let view = Viewport {
+        rows: 2,
+        ..Viewport::default()
+    };
+    let pos = Coord { x: 3, y: 5 };
+    assert_eq!(view.to_world(pos), Coord { x: 3, y: 10 });
+    assert_eq!(view.to_world_row(pos, 1), Coord { x: 3, y: 11 });
+    assert_eq!(view.pane_size(41), Coord { x: 41, y: 21 });
+}